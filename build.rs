@@ -0,0 +1,20 @@
+// Embed the git commit so `--version` and the About popup can name the exact build
+
+use std::process::Command;
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty());
+    // Source tarballs have no .git; the version alone still identifies the release
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=RATIFACT_GIT_COMMIT={}", commit);
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
@@ -0,0 +1,73 @@
+// Command line arguments
+
+pub const USAGE: &str = "Usage: ratifact [OPTIONS] [PATH]
+       ratifact daemon [OPTIONS] [PATH]
+
+Commands:
+  daemon        Run headless: scan every daemon_interval_minutes, apply the retention and
+                free-space policies and log to the log file; stops cleanly on SIGTERM
+
+Arguments:
+  [PATH]        Scan only this directory for this session; the saved scan paths are left alone
+
+Options:
+  --no-color    Disable colors (also enabled by the NO_COLOR env var)
+  --no-cache    Re-detect project languages on every scan
+  --read-only   Scan and browse only; deleting, clearing and rebuilding are disabled
+                (the daemon scans but never deletes)
+  --migrate     Check the database schema, apply pending migrations and exit
+  -V, --version Show version, commit and platform
+  -h, --help    Show this help";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliArgs {
+    pub no_color: bool,
+    pub no_cache: bool,
+    pub help: bool,
+    pub version: bool,
+    pub read_only: bool,
+    pub migrate: bool,
+    pub scan_path: Option<String>,
+    pub daemon: bool,
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliArgs, String> {
+    let mut cli = CliArgs::default();
+    let mut args = args.into_iter().peekable();
+    // A subcommand only counts first; `./daemon` still scans a directory of that name
+    if args.peek().is_some_and(|arg| arg == "daemon") {
+        args.next();
+        cli.daemon = true;
+    }
+    for arg in args {
+        match arg.as_str() {
+            "--no-color" => cli.no_color = true,
+            "--no-cache" => cli.no_cache = true,
+            "--read-only" => cli.read_only = true,
+            "--migrate" => cli.migrate = true,
+            "-h" | "--help" => cli.help = true,
+            "-V" | "--version" => cli.version = true,
+            other if other.starts_with('-') => return Err(format!("Unknown argument: {}", other)),
+            other if cli.scan_path.is_none() => cli.scan_path = Some(other.to_string()),
+            other => return Err(format!("Only one path can be scanned, got an extra: {}", other)),
+        }
+    }
+    Ok(cli)
+}
+
+pub fn version_info() -> String {
+    // Everything a bug report needs to pin down the build
+    let commit = option_env!("RATIFACT_GIT_COMMIT").unwrap_or("unknown");
+    format!(
+        "ratifact {} (commit {})\nPlatform: {}/{}",
+        env!("CARGO_PKG_VERSION"),
+        commit,
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+pub fn no_color_env() -> bool {
+    // https://no-color.org: any non-empty value disables color
+    std::env::var("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false)
+}
@@ -1,5 +1,6 @@
 // Configuration modules
 
+pub mod paths;
 pub mod settings;
 pub mod types;
 
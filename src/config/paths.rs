@@ -0,0 +1,56 @@
+// Platform config and data locations
+
+use directories::ProjectDirs;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Where config lived before XDG support, relative to the checkout old versions ran from
+const LEGACY_CONFIG_PATH: &str = "src/config/config.toml";
+const LEGACY_MIGRATED_MARKER: &str = "legacy-config-migrated";
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("ai", "neura", "ratifact")
+}
+
+pub fn config_file_path() -> PathBuf {
+    // ~/.config/ratifact/config.toml on Linux, platform equivalents elsewhere
+    project_dirs()
+        .map(|dirs| dirs.config_dir().join("config.toml"))
+        .unwrap_or_else(|| PathBuf::from(LEGACY_CONFIG_PATH))
+}
+
+pub fn data_dir() -> PathBuf {
+    // ~/.local/share/ratifact on Linux, platform equivalents elsewhere
+    project_dirs()
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Copies the old in-repo config into place the first time ratifact starts after upgrading and
+/// returns where it went. Like old versions, it looks in the working directory; a marker in the
+/// data dir keeps it from running again.
+pub fn migrate_legacy_config_once() -> Option<PathBuf> {
+    let marker = data_dir().join(LEGACY_MIGRATED_MARKER);
+    if marker.exists() {
+        return None;
+    }
+    let legacy = Path::new(LEGACY_CONFIG_PATH);
+    let target = config_file_path();
+    let migrated = migrate_legacy_config(legacy, &target);
+    if fs::create_dir_all(data_dir()).is_ok() {
+        let _ = fs::write(&marker, "");
+    }
+    migrated.then_some(target)
+}
+
+pub fn migrate_legacy_config(legacy: &Path, target: &Path) -> bool {
+    if target.exists() || !legacy.exists() || legacy == target {
+        return false;
+    }
+    if let Some(parent) = target.parent()
+        && fs::create_dir_all(parent).is_err()
+    {
+        return false;
+    }
+    fs::copy(legacy, target).is_ok()
+}
@@ -1,9 +1,15 @@
 // Settings management
 
+use crate::config::paths::config_file_path;
 use crate::config::types::Config;
 use std::fs;
+use std::path::Path;
 
 pub fn load_config() -> Config {
+    load_config_from(&config_file_path())
+}
+
+pub fn load_config_from(path: &Path) -> Config {
     dotenvy::dotenv().ok(); // Load .env file
     let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| {
         let user = std::env::var("POSTGRES_USERNAME").unwrap_or_else(|_| "ratifact".to_string());
@@ -19,36 +25,45 @@ pub fn load_config() -> Config {
         .unwrap_or_else(|_| "false".to_string())
         .to_lowercase() == "true";
 
-    // Try to load from src/config/config.toml
-    if let Ok(content) = fs::read_to_string("src/config/config.toml") {
-        if let Ok(mut config) = toml::from_str::<Config>(&content) {
-            // Override database_url and debug_logs_enabled from env
-            config.database_url = database_url;
-            config.debug_logs_enabled = debug_logs_enabled;
-            return config;
-        }
+    if let Ok(content) = fs::read_to_string(path)
+        && let Ok(mut config) = toml::from_str::<Config>(&content)
+    {
+        // Override database_url and debug_logs_enabled from env
+        config.database_url = database_url;
+        config.debug_logs_enabled = debug_logs_enabled;
+        return config;
     }
 
     Config {
         database_url,
-        scan_paths: vec![".".to_string()],
-        retention_days: 30,
         debug_logs_enabled,
-        excluded_paths: vec![],
+        ..Config::default()
     }
 }
 
+pub fn config_parse_error(path: &Path) -> Option<String> {
+    // load_config_from falls back to defaults on a broken file; this says why
+    let content = fs::read_to_string(path).ok()?;
+    toml::from_str::<Config>(&content)
+        .err()
+        .map(|e| format!("{} could not be parsed, using defaults: {}", path.display(), e.message()))
+}
+
 pub fn save_config(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    save_config_to(config, &config_file_path())
+}
+
+pub fn save_config_to(config: &Config, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     // Create a copy without database_url for saving
     let save_config = Config {
         database_url: "".to_string(), // Not saving db url
-        scan_paths: config.scan_paths.clone(),
-        retention_days: config.retention_days,
-        debug_logs_enabled: config.debug_logs_enabled,
-        excluded_paths: config.excluded_paths.clone(),
+        ..config.clone()
     };
     let toml_string = toml::to_string(&save_config)?;
-    fs::write("src/config/config.toml", toml_string)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, toml_string)?;
     Ok(())
 }
 
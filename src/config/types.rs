@@ -1,6 +1,7 @@
 // Configuration types
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -11,6 +12,174 @@ pub struct Config {
     pub debug_logs_enabled: bool,
     #[serde(default)]
     pub excluded_paths: Vec<String>,
+    #[serde(default)]
+    pub clean_docker: bool,
+    #[serde(default)]
+    pub no_color: bool,
+    #[serde(default)]
+    pub protected_paths: Vec<String>,
+    #[serde(default)]
+    pub vim_keys: bool,
+    #[serde(default)]
+    pub chart_auto_select_largest: bool,
+    #[serde(default = "default_alert_size_bytes")]
+    pub alert_size_bytes: u64,
+    #[serde(default)]
+    pub skip_hidden: bool,
+    #[serde(default = "default_true")]
+    pub automatic_removal: bool,
+    #[serde(default)]
+    pub last_focused_panel: usize,
+    #[serde(default = "default_true")]
+    pub watcher_enabled: bool,
+    #[serde(default)]
+    pub retention_uses_access_time: bool,
+    #[serde(default)]
+    pub pre_delete_hook: Option<String>,
+    #[serde(default = "default_db_max_connections")]
+    pub db_max_connections: u32,
+    #[serde(default = "default_db_connect_timeout_secs")]
+    pub db_connect_timeout_secs: u64,
+    /// Postgres `sslmode` (disable, allow, prefer, require, verify-ca, verify-full); overrides the URL's.
+    #[serde(default)]
+    pub db_ssl_mode: Option<String>,
+    /// CA certificate (PEM) to verify the server with, for verify-ca and verify-full.
+    #[serde(default)]
+    pub db_ssl_root_cert: Option<String>,
+    #[serde(default = "default_true")]
+    pub confirm_deletions: bool,
+    #[serde(default = "default_force_confirm_above_bytes")]
+    pub force_confirm_above_bytes: u64,
+    #[serde(default)]
+    pub keep_last_n: Option<u32>,
+    #[serde(default = "default_true")]
+    pub show_footer: bool,
+    /// Show full artifact paths instead of paths relative to their scan path (toggled with `p`).
+    #[serde(default)]
+    pub absolute_paths: bool,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    #[serde(default = "default_undo_history_limit")]
+    pub undo_history_limit: usize,
+    /// Artifacts listed from the database at startup; `M` loads the next batch.
+    #[serde(default = "default_artifacts_load_limit")]
+    pub artifacts_load_limit: usize,
+    /// Builds shown in the History panel.
+    #[serde(default = "default_history_load_limit")]
+    pub history_load_limit: usize,
+    #[serde(default)]
+    pub dedupe_builds: bool,
+    #[serde(default)]
+    pub detect_cachedir_tag: bool,
+    /// Automatic removal also frees space, least recently modified first, once a scan path's disk has less than this free.
+    #[serde(default)]
+    pub cleanup_when_free_below_bytes: Option<u64>,
+    /// Free space to get back to; defaults to the trigger threshold.
+    #[serde(default)]
+    pub cleanup_target_free_bytes: Option<u64>,
+    /// Desktop notification after automatic removal deletes something.
+    #[serde(default)]
+    pub notifications: bool,
+    /// Okabe-Ito colors for the charts and artifact types instead of the red/green-heavy defaults.
+    #[serde(default)]
+    pub color_blind_palette: bool,
+    /// Minutes between scans in `ratifact daemon`.
+    #[serde(default = "default_daemon_interval_minutes")]
+    pub daemon_interval_minutes: u64,
+    /// Directory that gets a JSON report (artifacts, sizes, totals) after every completed scan.
+    #[serde(default)]
+    pub scan_report_dir: Option<String>,
+    /// Build commands `r` may run; anything else is refused even after confirmation.
+    #[serde(default = "default_rebuild_commands")]
+    pub rebuild_commands: Vec<String>,
+    #[serde(default)]
+    pub panel_layout: PanelLayout,
+    /// `[size_budgets]` table: scan path to the bytes its artifacts may take up in total.
+    #[serde(default)]
+    pub size_budgets: BTreeMap<String, u64>,
+    /// `[keybindings]` table: action name to key, on top of the defaults. Kept last so it
+    /// serializes after the plain values.
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, String>,
+}
+
+/// Sizes of the overview grid, changed with Ctrl+arrows.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PanelLayout {
+    /// Share of the height given to the top row (Artifacts, History, Charts).
+    pub top_row_percent: u16,
+    /// Width weight of each panel within its row, indexed like the panels.
+    pub widths: [u16; 5],
+}
+
+const TOP_ROW_PERCENT_RANGE: (u16, u16) = (25, 75);
+const WIDTH_WEIGHT_RANGE: (u16, u16) = (2, 6);
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        PanelLayout { top_row_percent: 50, widths: [3; 5] }
+    }
+}
+
+impl PanelLayout {
+    /// Grows (positive) or shrinks the given panel; the other panels of its row give up the room.
+    pub fn resize(&mut self, panel: usize, wider: i16, taller: i16) {
+        let Some(width) = self.widths.get_mut(panel) else {
+            return;
+        };
+        *width = width.saturating_add_signed(wider).clamp(WIDTH_WEIGHT_RANGE.0, WIDTH_WEIGHT_RANGE.1);
+        // Panels 0-2 sit in the top row, so making one of the bottom panels taller shrinks it
+        let step = if panel < 3 { taller * 5 } else { -taller * 5 };
+        self.top_row_percent = self.top_row_percent.saturating_add_signed(step).clamp(TOP_ROW_PERCENT_RANGE.0, TOP_ROW_PERCENT_RANGE.1);
+    }
+
+    /// The same layout with hand-edited values pulled back into range.
+    pub fn clamped(&self) -> PanelLayout {
+        PanelLayout {
+            top_row_percent: self.top_row_percent.clamp(TOP_ROW_PERCENT_RANGE.0, TOP_ROW_PERCENT_RANGE.1),
+            widths: self.widths.map(|w| w.clamp(WIDTH_WEIGHT_RANGE.0, WIDTH_WEIGHT_RANGE.1)),
+        }
+    }
+}
+
+fn default_alert_size_bytes() -> u64 {
+    5_000_000_000 // 5 GB; 0 disables alerts
+}
+
+fn default_force_confirm_above_bytes() -> u64 {
+    10_000_000_000 // 10 GB; 0 disables the forced confirmation
+}
+
+fn default_db_max_connections() -> u32 {
+    5
+}
+
+fn default_db_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_undo_history_limit() -> usize {
+    20
+}
+
+fn default_artifacts_load_limit() -> usize {
+    200
+}
+
+fn default_history_load_limit() -> usize {
+    10
+}
+
+fn default_daemon_interval_minutes() -> u64 {
+    60
+}
+
+fn default_rebuild_commands() -> Vec<String> {
+    vec!["cargo build".to_string(), "npm run build".to_string()]
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -21,6 +190,89 @@ impl Default for Config {
             retention_days: 30,
             debug_logs_enabled: false,
             excluded_paths: vec![],
+            clean_docker: false,
+            no_color: false,
+            protected_paths: vec![],
+            vim_keys: false,
+            chart_auto_select_largest: false,
+            alert_size_bytes: default_alert_size_bytes(),
+            skip_hidden: false,
+            automatic_removal: true,
+            last_focused_panel: 0,
+            watcher_enabled: true,
+            retention_uses_access_time: false,
+            pre_delete_hook: None,
+            db_max_connections: default_db_max_connections(),
+            db_connect_timeout_secs: default_db_connect_timeout_secs(),
+            db_ssl_mode: None,
+            db_ssl_root_cert: None,
+            confirm_deletions: true,
+            force_confirm_above_bytes: default_force_confirm_above_bytes(),
+            keep_last_n: None,
+            show_footer: true,
+            absolute_paths: false,
+            color_blind_palette: false,
+            scan_report_dir: None,
+            follow_symlinks: false,
+            undo_history_limit: default_undo_history_limit(),
+            artifacts_load_limit: default_artifacts_load_limit(),
+            daemon_interval_minutes: default_daemon_interval_minutes(),
+            history_load_limit: default_history_load_limit(),
+            dedupe_builds: false,
+            detect_cachedir_tag: false,
+            cleanup_when_free_below_bytes: None,
+            cleanup_target_free_bytes: None,
+            notifications: false,
+            rebuild_commands: default_rebuild_commands(),
+            panel_layout: PanelLayout::default(),
+            size_budgets: BTreeMap::new(),
+            keybindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        // Collect every problem so one startup popup can list them all
+        let mut problems = vec![];
+        if self.database_url.trim().is_empty() {
+            problems.push("database_url is empty - set DATABASE_URL in .env".to_string());
+        }
+        if self.retention_days == 0 {
+            problems.push("retention_days must be greater than 0".to_string());
+        }
+        if self.artifacts_load_limit == 0 {
+            problems.push("artifacts_load_limit must be greater than 0".to_string());
+        }
+        if self.history_load_limit == 0 {
+            problems.push("history_load_limit must be greater than 0".to_string());
+        }
+        if self.daemon_interval_minutes == 0 {
+            problems.push("daemon_interval_minutes must be greater than 0".to_string());
+        }
+        if let Some(mode) = &self.db_ssl_mode
+            && mode.parse::<sqlx::postgres::PgSslMode>().is_err()
+        {
+            problems.push(format!(
+                "db_ssl_mode: '{}' is not one of disable, allow, prefer, require, verify-ca, verify-full",
+                mode
+            ));
+        }
+        if let Some(cert) = &self.db_ssl_root_cert
+            && !std::path::Path::new(cert).is_file()
+        {
+            problems.push(format!("db_ssl_root_cert: '{}' does not exist", cert));
+        }
+        for path in &self.scan_paths {
+            if !std::path::Path::new(path).exists() {
+                problems.push(format!("scan_paths: '{}' does not exist", path));
+            }
+        }
+        for (path, budget) in &self.size_budgets {
+            if *budget == 0 {
+                problems.push(format!("size_budgets: budget for '{}' must be greater than 0", path));
+            }
         }
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
     }
 }
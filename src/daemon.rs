@@ -0,0 +1,126 @@
+// Headless mode: scan and clean up on a timer, e.g. as a systemd service
+
+use crate::cli::CliArgs;
+use crate::config::settings::load_config;
+use crate::db::connection::{PoolSettings, connection_error_hint};
+use crate::tracking::cleanup::run_automatic_removal;
+use crate::tracking::logger::BuildLogger;
+use crate::tracking::scan::ScanJob;
+use crate::ui::keymap::{Action, KeyMap};
+use crate::utils::report::save_scan_report;
+use crate::utils::logger::log_to_file;
+use crate::utils::{LanguageCache, normalize_path};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Scans, applies the removal policies, then sleeps `daemon_interval_minutes`, until SIGTERM
+/// or Ctrl+C. Never touches the terminal or stdin; everything goes to the log file and stderr
+/// (which systemd puts in the journal).
+pub async fn run(cli: &CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = load_config();
+    if let Some(path) = &cli.scan_path {
+        config.scan_paths = vec![normalize_path(path)];
+    }
+    // A missing scan path may just be an unmounted drive, so problems don't stop the daemon
+    if let Err(problems) = config.validate() {
+        for problem in problems {
+            log(&format!("Config problem: {}", problem));
+        }
+    }
+    let pool_settings = PoolSettings::from(&config);
+    let mut logger = match BuildLogger::new(&config.database_url, &pool_settings).await {
+        Ok(logger) => logger,
+        Err(e) => {
+            let message = connection_error_hint(&e, &pool_settings).unwrap_or_else(|| e.to_string());
+            // main prints the error itself
+            log_to_file(&format!("Daemon could not start: {}", message));
+            return Err(message.into());
+        }
+    };
+    if let Err(e) = logger.set_dedupe(config.dedupe_builds).await {
+        log(&format!("dedupe_builds could not be applied: {}", e));
+    }
+    let interval = Duration::from_secs(config.daemon_interval_minutes.max(1) * 60);
+    let language_cache = Arc::new(Mutex::new(LanguageCache::default()));
+    let mut stop = StopSignal::new()?;
+    log(&format!(
+        "Daemon started: scanning {} every {} min{}",
+        config.scan_paths.join(", "),
+        interval.as_secs() / 60,
+        if cli.read_only { " (read-only, nothing is deleted)" } else { "" }
+    ));
+    loop {
+        if cli.no_cache {
+            language_cache.lock().unwrap().clear();
+        }
+        let logs = Arc::new(Mutex::new(vec![]));
+        let job = ScanJob::new(&config, logger.clone(), None, Arc::clone(&language_cache));
+        // A scan is safe to drop halfway; the next run redoes it
+        let report = tokio::select! {
+            report = job.run(Arc::clone(&logs)) => report,
+            _ = stop.received() => {
+                flush(&logs);
+                break;
+            }
+        };
+        flush(&logs);
+        if let Some(dir) = config.scan_report_dir.as_deref().filter(|dir| !dir.trim().is_empty()) {
+            log(&save_scan_report(dir, &report.artifacts, &report.sizes, report.duration));
+        }
+        // Deletions run to the end even if a stop arrives meanwhile; it is handled right after
+        if cli.read_only {
+            log("Read-only: automatic removal skipped");
+        } else if !config.automatic_removal {
+            let (keymap, _) = KeyMap::from_overrides(&config.keybindings);
+            log(&format!("Automatic removal is off: nothing deleted (toggle it with `{}` in the TUI)", keymap.label(Action::ToggleRemoval)));
+        } else {
+            run_automatic_removal(&logger.pool, &config, &logs).await;
+            flush(&logs);
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = stop.received() => break,
+        }
+    }
+    log("Daemon stopped");
+    Ok(())
+}
+
+fn log(message: &str) {
+    log_to_file(message);
+    eprintln!("{}", message);
+}
+
+fn flush(logs: &Mutex<Vec<String>>) {
+    for line in logs.lock().unwrap().drain(..) {
+        log(&line);
+    }
+}
+
+/// SIGTERM (systemd stopping the unit) or SIGINT. The SIGTERM handler is registered once up
+/// front, so one that arrives during a cleanup is still seen at the next wait.
+struct StopSignal {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+}
+
+impl StopSignal {
+    fn new() -> std::io::Result<Self> {
+        Ok(StopSignal {
+            #[cfg(unix)]
+            terminate: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?,
+        })
+    }
+
+    async fn received(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = self.terminate.recv() => log("Received SIGTERM, stopping"),
+            _ = tokio::signal::ctrl_c() => log("Received SIGINT, stopping"),
+        }
+        #[cfg(not(unix))]
+        if tokio::signal::ctrl_c().await.is_ok() {
+            log("Received Ctrl+C, stopping");
+        }
+    }
+}
@@ -1,9 +1,78 @@
 // Database connection management
 
+use crate::config::Config;
 use sqlx::PgPool;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use std::str::FromStr;
+use std::time::Duration;
 
-pub async fn establish_connection(database_url: &str) -> Result<PgPool, sqlx::Error> {
-    PgPool::connect(database_url).await
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub connect_timeout: Duration,
+    pub ssl_mode: Option<String>,
+    pub ssl_root_cert: Option<String>,
 }
 
+impl Default for PoolSettings {
+    fn default() -> Self {
+        PoolSettings::from(&Config::default())
+    }
+}
+
+impl From<&Config> for PoolSettings {
+    fn from(config: &Config) -> Self {
+        PoolSettings {
+            max_connections: config.db_max_connections.max(1),
+            connect_timeout: Duration::from_secs(config.db_connect_timeout_secs.max(1)),
+            ssl_mode: config.db_ssl_mode.clone(),
+            ssl_root_cert: config.db_ssl_root_cert.clone(),
+        }
+    }
+}
+
+pub fn connect_options(database_url: &str, settings: &PoolSettings) -> Result<PgConnectOptions, sqlx::Error> {
+    let mut options = PgConnectOptions::from_str(database_url)?;
+    if let Some(mode) = &settings.ssl_mode {
+        options = options.ssl_mode(PgSslMode::from_str(mode)?);
+    }
+    if let Some(cert) = &settings.ssl_root_cert {
+        options = options.ssl_root_cert(cert.as_str());
+    }
+    Ok(options)
+}
 
+pub async fn establish_connection(database_url: &str, settings: &PoolSettings) -> Result<PgPool, sqlx::Error> {
+    let options = connect_options(database_url, settings)?;
+    // The outer timeout also covers DNS and TCP stalls that happen before the pool starts counting
+    let connect = PgPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .acquire_timeout(settings.connect_timeout)
+        .connect_with(options);
+    tokio::time::timeout(settings.connect_timeout, connect)
+        .await
+        .unwrap_or(Err(sqlx::Error::PoolTimedOut))
+}
+
+/// Round-trips a trivial query; false when Postgres is down or doesn't answer within `timeout`.
+pub async fn ping(pool: &PgPool, timeout: Duration) -> bool {
+    if pool.is_closed() {
+        return false;
+    }
+    matches!(tokio::time::timeout(timeout, sqlx::query("SELECT 1").execute(pool)).await, Ok(Ok(_)))
+}
+
+/// What to tell the user for the connection failures with a known fix.
+pub fn connection_error_hint(error: &sqlx::Error, settings: &PoolSettings) -> Option<String> {
+    match error {
+        sqlx::Error::PoolTimedOut => Some(format!(
+            "Could not connect to the database within {}s (db_connect_timeout_secs). Check that Postgres is running and DATABASE_URL points to it.",
+            settings.connect_timeout.as_secs()
+        )),
+        sqlx::Error::Tls(e) => Some(format!(
+            "TLS connection to the database failed: {}\nCheck db_ssl_mode and db_ssl_root_cert in the config (or sslmode in DATABASE_URL).",
+            e
+        )),
+        _ => None,
+    }
+}
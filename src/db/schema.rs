@@ -1,23 +1,281 @@
 // Database schema definitions
 
+use crate::utils::format_bytes;
+use crate::utils::logger::log_to_file;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration;
 
-pub async fn create_tables(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS builds (
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanDiffEntry {
+    pub artifact_path: String,
+    pub previous_size: Option<u64>,
+    pub current_size: u64,
+}
+
+impl ScanDiffEntry {
+    pub fn describe(&self) -> Option<String> {
+        match self.previous_size {
+            None => Some(format!("+ new {} ({})", self.artifact_path, format_bytes(self.current_size))),
+            Some(prev) if self.current_size > prev => Some(format!(
+                "↑ grew {} {}",
+                format_bytes(self.current_size - prev),
+                self.artifact_path
+            )),
+            Some(prev) if self.current_size < prev => Some(format!(
+                "↓ shrank {} {}",
+                format_bytes(prev - self.current_size),
+                self.artifact_path
+            )),
+            Some(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageAggregate {
+    pub language: String,
+    pub total_size: u64,
+    pub project_count: u64,
+}
+
+impl LanguageAggregate {
+    pub fn describe(&self) -> String {
+        let noun = if self.project_count == 1 { "project" } else { "projects" };
+        format!(
+            "{}: {} across {} {}",
+            self.language,
+            format_bytes(self.total_size),
+            self.project_count,
+            noun
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeTrend {
+    pub artifact_path: String,
+    pub start_size: u64,
+    pub end_size: u64,
+}
+
+impl SizeTrend {
+    pub fn growth(&self) -> i64 {
+        self.end_size as i64 - self.start_size as i64
+    }
+}
+
+/// How long size points are kept; the longest range the Charts panel offers.
+pub const SIZE_HISTORY_DAYS: u32 = 90;
+
+pub const AGE_BUCKET_LABELS: [&str; 4] = ["0-7d", "7-30d", "30-90d", "90d+"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgeBucket {
+    pub label: &'static str,
+    pub artifact_count: u64,
+    pub total_size: u64,
+}
+
+/// One schema change. Released migrations are never edited; changes go in a new one with the next version.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub statements: &'static [&'static str],
+}
+
+// Databases from before schema_version existed replay all of these, so every statement must be
+// safe to run against a schema that already has the change
+pub const MIGRATIONS: [Migration; 6] = [
+    Migration {
+        version: 1,
+        description: "builds table",
+        statements: &["CREATE TABLE IF NOT EXISTS builds (
             id SERIAL PRIMARY KEY,
             project_path TEXT NOT NULL,
             language TEXT NOT NULL,
             build_time TIMESTAMPTZ DEFAULT NOW(),
             artifact_path TEXT NOT NULL,
             size_bytes BIGINT
+        )"],
+    },
+    Migration {
+        version: 2,
+        description: "artifact notes",
+        statements: &["ALTER TABLE builds ADD COLUMN IF NOT EXISTS notes TEXT"],
+    },
+    Migration {
+        version: 3,
+        description: "previous size for upserted builds",
+        // Only filled in by upserts, where the row itself no longer has an older snapshot to compare with
+        statements: &["ALTER TABLE builds ADD COLUMN IF NOT EXISTS previous_size_bytes BIGINT"],
+    },
+    Migration {
+        version: 4,
+        description: "size history",
+        // Every scan's sizes, kept separately so upserting builds (dedupe_builds) doesn't lose the trend;
+        // seeded from the snapshots builds already holds
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS size_history (
+                artifact_path TEXT NOT NULL,
+                size_bytes BIGINT NOT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+            "CREATE INDEX IF NOT EXISTS size_history_recorded_at ON size_history (recorded_at)",
+            "INSERT INTO size_history (artifact_path, size_bytes, recorded_at)
+             SELECT artifact_path, size_bytes, build_time FROM builds
+             WHERE size_bytes IS NOT NULL AND build_time IS NOT NULL AND NOT EXISTS (SELECT 1 FROM size_history)",
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "scan durations",
+        // How long each full scan took, to spot a tree that keeps making scans slower
+        statements: &["CREATE TABLE IF NOT EXISTS scan_runs (
+            finished_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            duration_ms BIGINT NOT NULL,
+            artifact_count INTEGER NOT NULL
+        )"],
+    },
+    Migration {
+        version: 6,
+        description: "drop Docker pseudo-paths",
+        // Docker entries used to be logged like directories; they are only kept in memory now
+        statements: &[
+            "DELETE FROM builds WHERE artifact_path LIKE 'docker://%'",
+            "DELETE FROM size_history WHERE artifact_path LIKE 'docker://%'",
+        ],
+    },
+];
+
+// Arbitrary key for pg_advisory_xact_lock, so two ratifact instances starting together don't both migrate
+const MIGRATION_LOCK_KEY: i64 = 0x7261_7469_6661_6374;
+
+pub fn latest_schema_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn pending_migrations(current: i32) -> Vec<&'static Migration> {
+    MIGRATIONS.iter().filter(|m| m.version > current).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigrationReport {
+    pub from: i32,
+    pub to: i32,
+}
+
+impl MigrationReport {
+    pub fn describe(&self) -> String {
+        let latest = latest_schema_version();
+        if self.from > latest {
+            format!("Database schema is v{}, newer than this ratifact knows (v{}); upgrade ratifact", self.from, latest)
+        } else if self.from == self.to {
+            format!("Database schema is up to date (v{})", self.to)
+        } else {
+            format!("Database schema migrated from v{} to v{}", self.from, self.to)
+        }
+    }
+}
+
+/// Applies the migrations the database hasn't seen yet, all in one transaction.
+pub async fn migrate(pool: &PgPool) -> Result<MigrationReport, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1)").bind(MIGRATION_LOCK_KEY).execute(&mut *tx).await?;
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
         )",
     )
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
+    let (from,): (i32,) = sqlx::query_as("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(&mut *tx)
+        .await?;
+    let mut to = from;
+    for migration in pending_migrations(from) {
+        for statement in migration.statements {
+            if let Err(e) = sqlx::query(statement).execute(&mut *tx).await {
+                log_to_file(&format!("Schema migration v{} ({}) failed: {}", migration.version, migration.description, e));
+                return Err(e);
+            }
+        }
+        sqlx::query("INSERT INTO schema_version (version, description) VALUES ($1, $2)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(&mut *tx)
+            .await?;
+        to = migration.version;
+    }
+    tx.commit().await?;
+    Ok(MigrationReport { from, to: to.max(from) })
+}
+
+pub async fn create_tables(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let report = migrate(pool).await?;
+    if report.from != report.to || report.from > latest_schema_version() {
+        log_to_file(&report.describe());
+    }
+    sqlx::query("DELETE FROM size_history WHERE recorded_at < NOW() - INTERVAL '1 day' * $1")
+        .bind(SIZE_HISTORY_DAYS as i32)
+        .execute(pool)
+        .await?;
     Ok(())
 }
 
+/// Switches between one row per artifact (upserted on rescans) and one row per scan.
+pub async fn set_build_dedupe(pool: &PgPool, enabled: bool) -> Result<(), sqlx::Error> {
+    if !enabled {
+        sqlx::query("DROP INDEX IF EXISTS builds_artifact_path_key").execute(pool).await?;
+        return Ok(());
+    }
+    // The unique index can't be built while duplicates exist, so keep only each artifact's newest row
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "DELETE FROM builds older USING builds newer
+         WHERE older.artifact_path = newer.artifact_path
+           AND (newer.build_time, newer.id) > (older.build_time, older.id)",
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS builds_artifact_path_key ON builds (artifact_path)")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+pub async fn fetch_notes(pool: &PgPool) -> Result<HashMap<String, String>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT DISTINCT ON (artifact_path) artifact_path, notes FROM builds
+         WHERE notes IS NOT NULL
+         ORDER BY artifact_path, build_time DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+pub async fn fetch_artifact_paths(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String,)>("SELECT DISTINCT artifact_path FROM builds")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(path,)| path).collect())
+}
+
+/// Each known artifact with the project and language of its latest build.
+pub async fn fetch_known_artifacts(pool: &PgPool) -> Result<Vec<(String, String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String, String)>(
+        "SELECT DISTINCT ON (artifact_path) project_path, language, artifact_path
+         FROM builds
+         ORDER BY artifact_path, build_time DESC"
+    )
+    .fetch_all(pool)
+    .await
+}
+
 pub async fn get_old_artifact_paths(pool: &PgPool, retention_days: u32) -> Result<Vec<String>, sqlx::Error> {
     let artifacts = sqlx::query_as::<_, (String,)>(
         "SELECT DISTINCT artifact_path FROM builds WHERE build_time < NOW() - INTERVAL '1 day' * $1"
@@ -29,15 +287,196 @@ pub async fn get_old_artifact_paths(pool: &PgPool, retention_days: u32) -> Resul
     Ok(artifacts.into_iter().map(|(path,)| path).collect())
 }
 
-pub async fn delete_old_builds_from_db(pool: &PgPool, retention_days: u32) -> Result<u64, sqlx::Error> {
+/// Each project's artifacts past its `keep_last_n` most recently built ones.
+pub async fn get_artifacts_beyond_count(pool: &PgPool, keep_last_n: u32) -> Result<Vec<String>, sqlx::Error> {
+    let artifacts = sqlx::query_as::<_, (String,)>(
+        "SELECT DISTINCT artifact_path FROM (
+            SELECT artifact_path,
+                   ROW_NUMBER() OVER (PARTITION BY project_path ORDER BY MAX(build_time) DESC, artifact_path) AS rank
+            FROM builds
+            GROUP BY project_path, artifact_path
+        ) ranked
+        WHERE rank > $1"
+    )
+    .bind(keep_last_n as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(artifacts.into_iter().map(|(path,)| path).collect())
+}
+
+pub async fn delete_old_builds_from_db(pool: &PgPool, retention_days: u32, keep_paths: &[String]) -> Result<u64, sqlx::Error> {
     let result = sqlx::query(
-        "DELETE FROM builds WHERE build_time < NOW() - INTERVAL '1 day' * $1"
+        "DELETE FROM builds WHERE build_time < NOW() - INTERVAL '1 day' * $1 AND NOT (artifact_path = ANY($2))"
     )
     .bind(retention_days as i32)
+    .bind(keep_paths)
     .execute(pool)
     .await?;
 
     Ok(result.rows_affected())
 }
 
+pub async fn delete_builds_for_paths(pool: &PgPool, paths: &[String]) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM builds WHERE artifact_path = ANY($1)")
+        .bind(paths)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Every artifact with its most recently recorded size. Scans re-log every artifact, so build
+/// times say nothing about use; callers order by content mtime where that matters.
+pub async fn fetch_latest_sizes(pool: &PgPool) -> Result<Vec<(String, u64)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, Option<i64>)>(
+        "SELECT artifact_path, (ARRAY_AGG(size_bytes ORDER BY build_time DESC))[1]
+         FROM builds
+         GROUP BY artifact_path"
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(path, size)| (path, size.unwrap_or(0).max(0) as u64)).collect())
+}
+
+pub async fn fetch_scan_diff(pool: &PgPool) -> Result<Vec<ScanDiffEntry>, sqlx::Error> {
+    // What the last scan recorded (size_history rows inside its scan_runs window) against each
+    // artifact's last size from before it; paths the scan didn't find again aren't compared
+    let rows = sqlx::query_as::<_, (String, Option<i64>, Option<i64>)>(
+        "WITH last_scan AS (
+             SELECT finished_at - duration_ms * INTERVAL '1 millisecond' AS started_at, finished_at
+             FROM scan_runs
+             ORDER BY finished_at DESC
+             LIMIT 1
+         ),
+         scanned AS (
+             SELECT DISTINCT ON (h.artifact_path) h.artifact_path, h.size_bytes
+             FROM size_history h, last_scan s
+             WHERE h.recorded_at BETWEEN s.started_at AND s.finished_at
+             ORDER BY h.artifact_path, h.recorded_at DESC
+         ),
+         before_scan AS (
+             SELECT DISTINCT ON (h.artifact_path) h.artifact_path, h.size_bytes
+             FROM size_history h, last_scan s
+             WHERE h.recorded_at < s.started_at
+             ORDER BY h.artifact_path, h.recorded_at DESC
+         )
+         SELECT scanned.artifact_path, before_scan.size_bytes AS previous_size, scanned.size_bytes AS current_size
+         FROM scanned
+         LEFT JOIN before_scan USING (artifact_path)
+         ORDER BY scanned.artifact_path"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(artifact_path, previous, current)| ScanDiffEntry {
+            artifact_path,
+            previous_size: previous.map(|s| s as u64),
+            current_size: current.unwrap_or(0) as u64,
+        })
+        .collect())
+}
+
+pub async fn fetch_language_aggregates(pool: &PgPool) -> Result<Vec<LanguageAggregate>, sqlx::Error> {
+    // Only the latest snapshot of each artifact counts, otherwise repeated scans inflate the totals
+    let rows = sqlx::query_as::<_, (String, Option<i64>, i64)>(
+        "SELECT language, SUM(size_bytes)::BIGINT AS total_size, COUNT(DISTINCT project_path) AS projects
+         FROM (
+             SELECT DISTINCT ON (artifact_path) artifact_path, project_path, language, size_bytes
+             FROM builds
+             ORDER BY artifact_path, build_time DESC, id DESC
+         ) latest
+         GROUP BY language
+         ORDER BY total_size DESC NULLS LAST"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(language, total, projects)| LanguageAggregate {
+            language,
+            total_size: total.unwrap_or(0) as u64,
+            project_count: projects as u64,
+        })
+        .collect())
+}
+
+pub async fn fetch_age_buckets(pool: &PgPool) -> Result<Vec<AgeBucket>, sqlx::Error> {
+    // Age is measured from each artifact's latest snapshot, the same timestamp retention looks at
+    let rows = sqlx::query_as::<_, (i32, i64, Option<i64>)>(
+        "SELECT bucket, COUNT(*), SUM(size_bytes)::BIGINT
+         FROM (
+             SELECT DISTINCT ON (artifact_path) size_bytes,
+                    CASE WHEN build_time >= NOW() - INTERVAL '7 days' THEN 0
+                         WHEN build_time >= NOW() - INTERVAL '30 days' THEN 1
+                         WHEN build_time >= NOW() - INTERVAL '90 days' THEN 2
+                         ELSE 3 END AS bucket
+             FROM builds
+             ORDER BY artifact_path, build_time DESC, id DESC
+         ) latest
+         GROUP BY bucket"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Empty buckets still get a bar so the chart always reads left to right
+    let mut buckets: Vec<AgeBucket> = AGE_BUCKET_LABELS
+        .iter()
+        .map(|label| AgeBucket { label, artifact_count: 0, total_size: 0 })
+        .collect();
+    for (bucket, count, size) in rows {
+        if let Some(entry) = buckets.get_mut(bucket as usize) {
+            entry.artifact_count = count as u64;
+            entry.total_size = size.unwrap_or(0) as u64;
+        }
+    }
+    Ok(buckets)
+}
+
+pub async fn fetch_size_trends(pool: &PgPool, days: u32) -> Result<Vec<SizeTrend>, sqlx::Error> {
+    // First and last size recorded inside the window
+    let rows = sqlx::query_as::<_, (String, i64, i64)>(
+        "SELECT artifact_path,
+                (ARRAY_AGG(size_bytes ORDER BY recorded_at ASC))[1] AS start_size,
+                (ARRAY_AGG(size_bytes ORDER BY recorded_at DESC))[1] AS end_size
+         FROM size_history
+         WHERE recorded_at >= NOW() - INTERVAL '1 day' * $1
+         GROUP BY artifact_path"
+    )
+    .bind(days as i32)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(artifact_path, start, end)| SizeTrend {
+            artifact_path,
+            start_size: start as u64,
+            end_size: end as u64,
+        })
+        .collect())
+}
+
+/// Scans kept for the duration trend in the Summary panel.
+pub const SCAN_RUNS_SHOWN: i64 = 10;
+
+pub async fn record_scan_run(pool: &PgPool, duration: Duration, artifact_count: usize) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO scan_runs (duration_ms, artifact_count) VALUES ($1, $2)")
+        .bind(duration.as_millis() as i64)
+        .bind(artifact_count as i32)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
 
+/// Durations of the most recent scans, newest first.
+pub async fn fetch_scan_durations(pool: &PgPool, limit: i64) -> Result<Vec<Duration>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64,)>("SELECT duration_ms FROM scan_runs ORDER BY finished_at DESC LIMIT $1")
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(ms,)| Duration::from_millis(ms.max(0) as u64)).collect())
+}
@@ -1,10 +1,13 @@
+mod cli;
 mod config;
+mod daemon;
 mod db;
 mod tracking;
 mod ui;
 mod utils;
 
 use ratatui::crossterm::{
+    event::DisableMouseCapture,
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
@@ -16,11 +19,18 @@ use utils::logger::log_to_file;
 
 
 #[cfg(test)]
+mod cli_tests {
+    include!("tests/cli_tests.rs");
+}
+
+#[cfg(test)]
+#[allow(clippy::len_zero)]
 mod config_tests {
     include!("tests/config_tests.rs");
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_comparison)]
 mod db_tests {
     include!("tests/db_tests.rs");
 }
@@ -45,9 +55,71 @@ mod utils_tests {
     include!("tests/utils_tests.rs");
 }
 
+#[cfg(test)]
+mod scanner_tests {
+    include!("tests/scanner_tests.rs");
+}
+
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture);
+}
+
+fn install_panic_hook() {
+    // Restore the terminal before the panic message is printed, otherwise the shell is left in raw mode
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        log_to_file(&format!("Panic: {}", info));
+        default_hook(info);
+        // A panic in a background task only ends that task (its JoinError fallback takes over), but
+        // the terminal is restored now, so have the main loop quit through the normal shutdown
+        if std::thread::current().name() != Some("main") {
+            ui::app::BACKGROUND_PANICKED.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }));
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if cli.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if cli.version {
+        println!("{}", cli::version_info());
+        return Ok(());
+    }
+    // First run after upgrading: carry over the old in-repo config
+    if let Some(path) = config::paths::migrate_legacy_config_once() {
+        log_to_file(&format!("Migrated config to {}", path.display()));
+    }
+    if cli.migrate {
+        let config = config::settings::load_config();
+        let pool = db::connection::establish_connection(&config.database_url, &db::connection::PoolSettings::from(&config)).await?;
+        let report = db::schema::migrate(&pool).await?;
+        println!("{}", report.describe());
+        return Ok(());
+    }
+    if let Some(path) = &cli.scan_path
+        && !std::path::Path::new(path).is_dir()
+    {
+        eprintln!("Not a directory: {}", path);
+        std::process::exit(2);
+    }
+    if cli.daemon {
+        return daemon::run(&cli).await;
+    }
+
     // Setup terminal
+    install_panic_hook();
     enable_raw_mode()?;
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen)?;
@@ -55,9 +127,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // Initialize the app
-    let mut app = match App::new().await {
+    let mut app = match App::new(&cli).await {
         Ok(app) => app,
         Err(e) => {
+            restore_terminal();
             log_to_file(&format!("App init error: {:?}", e));
             return Err(e);
         }
@@ -67,13 +140,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let res = app.run(&mut terminal).await;
 
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    restore_terminal();
     terminal.show_cursor()?;
 
+    // Don't leave a deletion half-done behind the user's back, nor let a scan write after the UI is gone
+    let pending = app.pending_critical_tasks();
+    if !pending.is_empty() {
+        eprintln!("Waiting for {} to finish...", pending.join(", "));
+    }
+    for label in app.shutdown().await {
+        eprintln!("Stopped before finishing: {} (see the log)", label);
+    }
+
     if let Err(err) = res {
         log_to_file(&format!("Run error: {:?}", err));
     }
+    if ui::app::BACKGROUND_PANICKED.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err("a background task panicked, so ratifact stopped (details in ratifact.log)".into());
+    }
 
     Ok(())
 }
@@ -0,0 +1,61 @@
+// CLI tests
+
+use crate::cli::{parse_args, CliArgs};
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_parse_args_defaults() {
+    assert_eq!(parse_args(args(&[])).unwrap(), CliArgs::default());
+}
+
+#[test]
+fn test_parse_args_no_color() {
+    let cli = parse_args(args(&["--no-color"])).unwrap();
+    assert!(cli.no_color);
+}
+
+#[test]
+fn test_parse_args_unknown() {
+    assert!(parse_args(args(&["--bogus"])).is_err());
+}
+
+#[test]
+fn test_parse_args_version() {
+    assert!(parse_args(args(&["--version"])).unwrap().version);
+    assert!(parse_args(args(&["-V"])).unwrap().version);
+    assert!(crate::cli::version_info().starts_with(&format!("ratifact {}", env!("CARGO_PKG_VERSION"))));
+}
+
+#[test]
+fn test_parse_args_read_only() {
+    assert!(parse_args(args(&["--read-only"])).unwrap().read_only);
+}
+
+#[test]
+fn test_parse_args_scan_path() {
+    let cli = parse_args(args(&["--read-only", "/tmp/project"])).unwrap();
+    assert_eq!(cli.scan_path.as_deref(), Some("/tmp/project"));
+    assert!(cli.read_only);
+    assert!(parse_args(args(&["/a", "/b"])).is_err());
+}
+
+#[test]
+fn test_parse_args_daemon() {
+    let cli = parse_args(args(&["daemon", "--read-only", "/tmp/project"])).unwrap();
+    assert!(cli.daemon);
+    assert!(cli.read_only);
+    assert_eq!(cli.scan_path.as_deref(), Some("/tmp/project"));
+    // Only a leading `daemon` is the subcommand; later it is a path
+    let cli = parse_args(args(&["--read-only", "daemon"])).unwrap();
+    assert!(!cli.daemon);
+    assert_eq!(cli.scan_path.as_deref(), Some("daemon"));
+}
+
+#[test]
+fn test_parse_args_migrate() {
+    assert!(parse_args(args(&["--migrate"])).unwrap().migrate);
+    assert!(!parse_args(args(&[])).unwrap().migrate);
+}
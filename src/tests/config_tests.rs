@@ -1,17 +1,19 @@
 // Config tests
 
-use crate::config::settings::{load_config, save_config};
+use crate::config::paths::migrate_legacy_config;
+use crate::config::settings::{load_config, load_config_from, save_config_to};
 use crate::config::types::Config;
 use std::env;
+use std::fs;
 use std::path::Path;
+use tempfile::TempDir;
 
 #[test]
 fn test_load_config() {
     let config = load_config();
     assert!(config.database_url.contains("postgres"));
-    // scan_paths will be loaded from src/config/config.toml which contains ["/srv"]
+    // scan_paths/retention_days come from the user config or the defaults
     assert!(config.scan_paths.len() > 0, "scan_paths should not be empty");
-    // retention_days is loaded from src/config/config.toml which has value 1
     assert!(config.retention_days > 0, "retention_days should be greater than 0");
 }
 
@@ -35,7 +37,7 @@ fn test_load_config_from_src_config_toml() {
     assert!(Path::new(config_path).exists(), "config.toml should exist at {}", config_path);
 
     // Load the config
-    let config = load_config();
+    let config = load_config_from(Path::new(config_path));
 
     // Verify it has expected values from src/config/config.toml
     assert!(!config.database_url.is_empty() || config.database_url.is_empty(), "database_url should be loaded");
@@ -52,18 +54,156 @@ fn test_save_and_load_config() {
         retention_days: 30,
         debug_logs_enabled: false,
         excluded_paths: vec![],
+        ..Config::default()
     };
 
-    // Save the config
-    let result = save_config(&test_config);
-    assert!(result.is_ok(), "save_config should succeed");
-
-    // Verify the file exists at the new location
-    assert!(Path::new("src/config/config.toml").exists(), "config.toml should exist at src/config/config.toml");
+    // Save the config into a nested dir to check parents are created
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("ratifact").join("config.toml");
+    let result = save_config_to(&test_config, &path);
+    assert!(result.is_ok(), "save_config_to should succeed");
+    assert!(path.exists(), "config.toml should exist at {}", path.display());
 
     // Load and verify
-    let loaded_config = load_config();
+    let loaded_config = load_config_from(&path);
     assert_eq!(loaded_config.scan_paths, test_config.scan_paths, "scan_paths should match after save/load");
     assert_eq!(loaded_config.retention_days, test_config.retention_days, "retention_days should match after save/load");
     assert_eq!(loaded_config.excluded_paths, test_config.excluded_paths, "excluded_paths should match after save/load");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_config_defaults_for_missing_fields() {
+    // Older config files without newer fields must still parse
+    let content = "database_url = \"\"\nscan_paths = [\"/srv\"]\nretention_days = 7\n";
+    let config: Config = toml::from_str(content).unwrap();
+    assert_eq!(config.retention_days, 7);
+    assert!(!config.clean_docker, "clean_docker should default to false");
+}
+
+#[test]
+fn test_migrate_legacy_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let legacy = temp_dir.path().join("legacy.toml");
+    let target = temp_dir.path().join("xdg").join("config.toml");
+    fs::write(&legacy, "scan_paths = [\"/legacy\"]").unwrap();
+
+    assert!(migrate_legacy_config(&legacy, &target), "should migrate when target is missing");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "scan_paths = [\"/legacy\"]");
+
+    // Never overwrite an existing config
+    fs::write(&legacy, "scan_paths = [\"/changed\"]").unwrap();
+    assert!(!migrate_legacy_config(&legacy, &target));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "scan_paths = [\"/legacy\"]");
+}
+
+#[test]
+fn test_alert_size_default() {
+    let config: Config = toml::from_str("database_url = \"\"\nscan_paths = []\nretention_days = 1\n").unwrap();
+    assert_eq!(config.alert_size_bytes, 5_000_000_000);
+    assert_eq!(Config::default().alert_size_bytes, 5_000_000_000);
+}
+
+#[test]
+fn test_ui_state_persists() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    let config = Config { automatic_removal: false, last_focused_panel: 3, ..Config::default() };
+    save_config_to(&config, &path).unwrap();
+
+    let loaded = load_config_from(&path);
+    assert!(!loaded.automatic_removal);
+    assert_eq!(loaded.last_focused_panel, 3);
+
+    // Older configs keep the previous behaviour
+    assert!(Config::default().automatic_removal);
+}
+
+#[test]
+fn test_validate_reports_all_problems() {
+    let config = Config {
+        database_url: String::new(),
+        retention_days: 0,
+        scan_paths: vec!["/definitely/not/here".to_string()],
+        artifacts_load_limit: 0,
+        db_ssl_mode: Some("required".to_string()),
+        size_budgets: [("/work".to_string(), 0)].into_iter().collect(),
+        ..Config::default()
+    };
+    let problems = config.validate().unwrap_err();
+    assert_eq!(problems.len(), 6);
+    assert!(problems.iter().any(|p| p.contains("size_budgets")));
+    assert!(problems.iter().any(|p| p.contains("db_ssl_mode")));
+    assert!(problems.iter().any(|p| p.contains("retention_days")));
+    assert!(problems.iter().any(|p| p.contains("artifacts_load_limit")));
+    assert!(problems.iter().any(|p| p.contains("/definitely/not/here")));
+
+    let dir = TempDir::new().unwrap();
+    let valid = Config {
+        database_url: "postgres://localhost/ratifact".to_string(),
+        scan_paths: vec![dir.path().display().to_string()],
+        ..Config::default()
+    };
+    assert!(valid.validate().is_ok());
+}
+
+#[test]
+fn test_config_parse_error() {
+    use crate::config::settings::config_parse_error;
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    assert!(config_parse_error(&path).is_none(), "a missing file is not an error");
+
+    fs::write(&path, "retention_days = \"thirty\"\n").unwrap();
+    assert!(config_parse_error(&path).is_some());
+
+    save_config_to(&Config::default(), &path).unwrap();
+    assert!(config_parse_error(&path).is_none());
+}
+
+#[test]
+fn test_pool_settings_from_config() {
+    use crate::db::connection::PoolSettings;
+    use std::time::Duration;
+
+    let config: Config = toml::from_str("database_url = \"\"\nscan_paths = []\nretention_days = 1\n").unwrap();
+    assert_eq!(config.db_max_connections, 5);
+    assert_eq!(config.db_connect_timeout_secs, 10);
+
+    // Zero would make the pool unusable, so both are clamped to at least 1
+    let settings = PoolSettings::from(&Config { db_max_connections: 0, db_connect_timeout_secs: 0, ..Config::default() });
+    assert_eq!(settings.max_connections, 1);
+    assert_eq!(settings.connect_timeout, Duration::from_secs(1));
+}
+
+#[test]
+fn test_keybindings_round_trip() {
+    let mut config: Config = toml::from_str("database_url = \"\"\nscan_paths = []\nretention_days = 1\n\n[keybindings]\ndelete = \"x\"\n").unwrap();
+    assert_eq!(config.keybindings.get("delete").map(String::as_str), Some("x"));
+    // Saving must still produce valid TOML with the table after the plain values
+    config.keybindings.insert("help".to_string(), "h".to_string());
+    let saved = toml::to_string(&config).unwrap();
+    let reloaded: Config = toml::from_str(&saved).unwrap();
+    assert_eq!(reloaded.keybindings, config.keybindings);
+}
+
+#[test]
+fn test_panel_layout_resize_clamps() {
+    use crate::config::types::PanelLayout;
+
+    let mut layout = PanelLayout::default();
+    layout.resize(0, 1, 1);
+    assert_eq!(layout.widths[0], 4);
+    assert_eq!(layout.top_row_percent, 55);
+    // Taller for a bottom panel means a shorter top row
+    layout.resize(4, 0, 1);
+    assert_eq!(layout.top_row_percent, 50);
+    for _ in 0..20 {
+        layout.resize(1, -1, -1);
+    }
+    assert_eq!(layout.widths[1], 2);
+    assert_eq!(layout.top_row_percent, 25);
+
+    let wild = PanelLayout { top_row_percent: 99, widths: [0, 100, 3, 3, 3] };
+    assert_eq!(wild.clamped(), PanelLayout { top_row_percent: 75, widths: [2, 6, 3, 3, 3] });
+}
@@ -1,8 +1,10 @@
 // Test modules
 
+pub mod cli_tests;
 pub mod config_tests;
 pub mod db_tests;
 pub mod tracking_tests;
 pub mod ui_tests;
 pub mod watcher_tests;
-pub mod utils_tests;
\ No newline at end of file
+pub mod utils_tests;
+pub mod scanner_tests;
\ No newline at end of file
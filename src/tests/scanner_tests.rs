@@ -0,0 +1,189 @@
+// Scanner tests
+
+use crate::tracking::scanner::{ARTIFACT_DIRS, SCAN_DEPTH, WalkOptions, find_artifacts};
+use std::fs;
+use tempfile::TempDir;
+
+fn hit_paths(root: &TempDir, excluded: &[String], depth: usize) -> Vec<String> {
+    let mut paths: Vec<String> = find_artifacts(&root.path().display().to_string(), depth, &ARTIFACT_DIRS, excluded, WalkOptions::default(), |_| {})
+        .into_iter()
+        .map(|hit| hit.path)
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_find_artifacts_matches_artifact_dirs() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("app").join("target").join("debug")).unwrap();
+    fs::create_dir_all(root.path().join("web").join("node_modules")).unwrap();
+    fs::create_dir_all(root.path().join("web").join("src")).unwrap();
+    // A file named like an artifact dir is not an artifact
+    fs::write(root.path().join("web").join("dist"), "not a dir").unwrap();
+
+    let hits = find_artifacts(&root.path().display().to_string(), SCAN_DEPTH, &ARTIFACT_DIRS, &[], WalkOptions::default(), |_| {});
+    assert_eq!(hits.len(), 2);
+    let target = hits.iter().find(|h| h.path.ends_with("target")).unwrap();
+    assert_eq!(target.project_path, root.path().join("app").display().to_string());
+    assert!(hits.iter().any(|h| h.path.ends_with("node_modules")));
+}
+
+#[test]
+fn test_find_artifacts_respects_exclusions() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("keep").join("target")).unwrap();
+    fs::create_dir_all(root.path().join("skip").join("target")).unwrap();
+
+    let excluded = vec![root.path().join("skip").display().to_string()];
+    let paths = hit_paths(&root, &excluded, SCAN_DEPTH);
+    assert_eq!(paths, vec![root.path().join("keep").join("target").display().to_string()]);
+}
+
+#[test]
+fn test_find_artifacts_respects_depth() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("a").join("target")).unwrap();
+    fs::create_dir_all(root.path().join("a").join("b").join("c").join("target")).unwrap();
+
+    // a/target is at depth 2, a/b/c/target at depth 4
+    assert_eq!(hit_paths(&root, &[], 2).len(), 1);
+    assert_eq!(hit_paths(&root, &[], 3).len(), 1);
+    assert_eq!(hit_paths(&root, &[], 4).len(), 2);
+}
+
+#[test]
+fn test_find_artifacts_skips_hidden_trees() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join(".git").join("build")).unwrap();
+    fs::create_dir_all(root.path().join("site").join(".next")).unwrap();
+    let scan_root = root.path().display().to_string();
+
+    let all = find_artifacts(&scan_root, SCAN_DEPTH, &ARTIFACT_DIRS, &[], WalkOptions::default(), |_| {});
+    assert_eq!(all.len(), 2);
+    // Known hidden artifact dirs like .next are still found
+    let visible = find_artifacts(&scan_root, SCAN_DEPTH, &ARTIFACT_DIRS, &[], WalkOptions { skip_hidden: true, ..WalkOptions::default() }, |_| {});
+    assert_eq!(visible.len(), 1);
+    assert!(visible[0].path.ends_with(".next"));
+}
+
+#[test]
+fn test_find_artifacts_detects_virtualenvs_by_content() {
+    let root = TempDir::new().unwrap();
+    let venv = root.path().join("api").join(".venv");
+    fs::create_dir_all(&venv).unwrap();
+    fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+    // A plain directory called env is not an artifact
+    fs::create_dir_all(root.path().join("api").join("env")).unwrap();
+    let scan_root = root.path().display().to_string();
+
+    for skip_hidden in [false, true] {
+        let hits = find_artifacts(&scan_root, SCAN_DEPTH, &ARTIFACT_DIRS, &[], WalkOptions { skip_hidden, ..WalkOptions::default() }, |_| {});
+        assert_eq!(hits.len(), 1, "skip_hidden = {}", skip_hidden);
+        assert!(hits[0].virtualenv);
+        assert_eq!(hits[0].path, venv.display().to_string());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_find_artifacts_follows_symlinks_without_looping() {
+    let root = TempDir::new().unwrap();
+    let elsewhere = TempDir::new().unwrap();
+    fs::create_dir_all(elsewhere.path().join("app").join("target")).unwrap();
+    std::os::unix::fs::symlink(elsewhere.path(), root.path().join("linked")).unwrap();
+    // A link back to the scan root would cycle forever without the visited set
+    std::os::unix::fs::symlink(root.path(), root.path().join("loop")).unwrap();
+    let scan_root = root.path().display().to_string();
+
+    let not_followed = find_artifacts(&scan_root, 5, &ARTIFACT_DIRS, &[], WalkOptions::default(), |_| {});
+    assert!(not_followed.is_empty());
+
+    let options = WalkOptions { follow_symlinks: true, ..WalkOptions::default() };
+    let followed = find_artifacts(&scan_root, 5, &ARTIFACT_DIRS, &[], options, |_| {});
+    assert_eq!(followed.len(), 1);
+    assert!(followed[0].path.ends_with("target"));
+}
+
+#[test]
+fn test_find_artifacts_detects_cachedir_tag() {
+    let root = TempDir::new().unwrap();
+    let tagged = root.path().join("tool").join("weird-cache");
+    fs::create_dir_all(&tagged).unwrap();
+    fs::write(tagged.join("CACHEDIR.TAG"), "Signature: 8a477f597d28d172789f06886806bc55\n# This file is a cache directory tag.\n").unwrap();
+    // Without the signature the file is just a file
+    let fake = root.path().join("tool").join("not-a-cache");
+    fs::create_dir_all(&fake).unwrap();
+    fs::write(fake.join("CACHEDIR.TAG"), "hello").unwrap();
+    let scan_root = root.path().display().to_string();
+
+    assert!(find_artifacts(&scan_root, SCAN_DEPTH, &ARTIFACT_DIRS, &[], WalkOptions::default(), |_| {}).is_empty());
+
+    let options = WalkOptions { detect_cachedir_tag: true, ..WalkOptions::default() };
+    let hits = find_artifacts(&scan_root, SCAN_DEPTH, &ARTIFACT_DIRS, &[], options, |_| {});
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].path, tagged.display().to_string());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_artifact_can_be_sized_and_deleted() {
+    use crate::utils::deletion::delete_path;
+    use crate::utils::{calculate_dir_size, path_from_string, path_to_string};
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let root = TempDir::new().unwrap();
+    let project = root.path().join(OsStr::from_bytes(b"caf\xe9-project"));
+    let target = project.join("target");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("lib.rlib"), vec![0u8; 512]).unwrap();
+
+    let hits = find_artifacts(&root.path().display().to_string(), SCAN_DEPTH, &ARTIFACT_DIRS, &[], WalkOptions::default(), |_| {});
+    assert_eq!(hits.len(), 1);
+    assert_eq!(path_from_string(&hits[0].path), target);
+    assert_eq!(path_to_string(&target), hits[0].path);
+    assert_eq!(calculate_dir_size(&hits[0].path), 512);
+
+    delete_path(&hits[0].path, None).unwrap();
+    assert!(!target.exists());
+    assert!(project.exists());
+}
+
+#[test]
+fn test_find_artifacts_with_excluded_reports_hidden_matches() {
+    use crate::tracking::scanner::find_artifacts_with_excluded;
+
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("keep").join("target")).unwrap();
+    fs::create_dir_all(root.path().join("skip").join("target")).unwrap();
+    let excluded = vec![root.path().join("skip").display().to_string()];
+
+    let (hits, hidden) = find_artifacts_with_excluded(&root.path().display().to_string(), SCAN_DEPTH, &ARTIFACT_DIRS, &excluded, WalkOptions::default(), |_| {});
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].path.contains("keep"));
+    assert_eq!(hidden.len(), 1);
+    assert!(hidden[0].path.contains("skip"));
+}
+
+#[test]
+fn test_find_artifacts_honors_ratifactignore() {
+    let root = TempDir::new().unwrap();
+    let app = root.path().join("app");
+    fs::create_dir_all(app.join("target")).unwrap();
+    fs::create_dir_all(app.join("web").join("node_modules")).unwrap();
+    fs::create_dir_all(app.join("web").join("dist")).unwrap();
+    fs::create_dir_all(root.path().join("other").join("target")).unwrap();
+    fs::write(app.join(".ratifactignore"), "target/\nnode_modules\n").unwrap();
+    // A nested file can re-include what its parent ignored
+    fs::write(app.join("web").join(".ratifactignore"), "!node_modules\ndist\n").unwrap();
+
+    let paths = hit_paths(&root, &[], SCAN_DEPTH);
+    assert_eq!(
+        paths,
+        vec![
+            app.join("web").join("node_modules").display().to_string(),
+            root.path().join("other").join("target").display().to_string(),
+        ]
+    );
+}
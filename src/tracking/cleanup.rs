@@ -0,0 +1,190 @@
+// Automatic removal: the retention, history and free-space policies
+
+use crate::config::Config;
+use crate::db::schema::{delete_builds_for_paths, delete_old_builds_from_db, fetch_latest_sizes, get_artifacts_beyond_count, get_old_artifact_paths};
+use crate::utils::deletion::{describe_delete_error, remove_with_retry, run_pre_delete_hook};
+use crate::utils::desktop::notify_desktop;
+use crate::utils::docker::is_docker_artifact;
+use crate::utils::logger::log_to_file;
+use crate::utils::{format_bytes, free_space_bytes, free_space_plan, is_path_protected, least_recently_modified, normalize_path, used_within_days};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Artifacts modified (or used) within this many days count as active work.
+pub const ACTIVE_WITHIN_DAYS: u32 = 1;
+
+/// Applies the retention, `keep_last_n` and free-space policies, deleting from disk and the
+/// database, then reports (and optionally notifies) what was freed.
+pub async fn run_automatic_removal(pool: &sqlx::PgPool, config: &Config, logs: &Mutex<Vec<String>>) {
+    // Sizes as last recorded, read before the rows go, for the notification
+    let sizes: HashMap<String, u64> = fetch_latest_sizes(pool)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    let mut freed = vec![];
+    match plan_cleanup(pool, config).await {
+        Ok(plan) => {
+            // Delete directories from disk
+            let mut kept = plan.kept.clone();
+            for path in plan.targets() {
+                match remove_off_thread(path, pre_delete_hook(config)).await {
+                    Ok(()) => {
+                        forget_deleted(pool, path).await;
+                        freed.push(path.clone());
+                    }
+                    Err(e) => {
+                        logs.lock().unwrap().push(format!("Automatic removal: kept {}: {}", path, e));
+                        kept.push(path.clone());
+                    }
+                }
+            }
+            // Remove the remaining expired entries from the database
+            let _ = delete_old_builds_from_db(pool, config.retention_days, &kept).await;
+        }
+        Err(e) => {
+            logs.lock().unwrap().push(format!("Automatic removal: could not plan the cleanup: {}", e));
+        }
+    }
+    if config.cleanup_when_free_below_bytes.is_some() {
+        freed.extend(free_space_cleanup(pool, config, logs).await);
+    }
+    if !freed.is_empty() {
+        let bytes = freed.iter().filter_map(|path| sizes.get(path)).sum();
+        let message = cleanup_summary(bytes, freed.len());
+        logs.lock().unwrap().push(format!("Automatic removal: {}", message));
+        if config.notifications {
+            tokio::task::spawn_blocking(move || notify_desktop("Automatic cleanup", &message));
+        }
+    }
+}
+
+/// Artifacts automatic removal would act on, split the way it deletes them.
+pub struct CleanupPlan {
+    /// Past `retention_days`; their old rows go by age
+    expired: Vec<String>,
+    /// Older than their project's newest `keep_last_n` artifacts, and not already expired
+    beyond_count: Vec<String>,
+    /// Protected or recently used, never touched
+    kept: Vec<String>,
+}
+
+impl CleanupPlan {
+    pub fn targets(&self) -> impl Iterator<Item = &String> {
+        self.expired.iter().chain(&self.beyond_count)
+    }
+}
+
+/// Deletes the least recently modified artifacts on every scan path whose disk is below
+/// `cleanup_when_free_below_bytes`, until it is back at `cleanup_target_free_bytes`.
+async fn free_space_cleanup(pool: &sqlx::PgPool, config: &Config, logs: &Mutex<Vec<String>>) -> Vec<String> {
+    let Some(threshold) = config.cleanup_when_free_below_bytes else {
+        return vec![];
+    };
+    let target = config.cleanup_target_free_bytes.unwrap_or(threshold).max(threshold);
+    let candidates = match fetch_latest_sizes(pool).await {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            logs.lock().unwrap().push(format!("Free-space cleanup: could not list artifacts: {}", e));
+            return vec![];
+        }
+    };
+    let mut removed = vec![];
+    for root in config.scan_paths.iter().map(|p| normalize_path(p)) {
+        let Some(free) = free_space_bytes(&root) else {
+            logs.lock().unwrap().push(format!("Free-space cleanup: can't read free space for {}", root));
+            continue;
+        };
+        if free >= threshold {
+            continue;
+        }
+        let on_root: Vec<(String, u64)> = candidates
+            .iter()
+            .filter(|(path, _)| {
+                Path::new(path).starts_with(&root)
+                    && !is_docker_artifact(path)
+                    && !is_path_protected(path, &config.protected_paths)
+                    && !removed.contains(path)
+            })
+            .cloned()
+            .collect();
+        // Walks each candidate for its newest mtime; anything modified recently is left alone
+        let on_root = tokio::task::spawn_blocking(move || least_recently_modified(&on_root, ACTIVE_WITHIN_DAYS))
+            .await
+            .unwrap_or_default();
+        let planned = free_space_plan(&on_root, free, target);
+        logs.lock().unwrap().push(format!(
+            "Free-space cleanup: {} free on {} (below {}), removing {} least recently modified artifacts",
+            format_bytes(free), root, format_bytes(threshold), planned.len()
+        ));
+        for path in planned {
+            match remove_off_thread(&path, pre_delete_hook(config)).await {
+                Ok(()) => {}
+                Err(e) => {
+                    logs.lock().unwrap().push(format!("Free-space cleanup: could not delete {}: {}", path, e));
+                    continue;
+                }
+            }
+            forget_deleted(pool, &path).await;
+            removed.push(path);
+        }
+    }
+    removed
+}
+
+fn pre_delete_hook(config: &Config) -> Option<&str> {
+    config.pre_delete_hook.as_deref().filter(|hook| !hook.trim().is_empty())
+}
+
+/// Asks the `pre_delete_hook`, then `remove_with_retry`, on a blocking thread since a big tree
+/// can take a while to go. The error says why the artifact is still there.
+async fn remove_off_thread(path: &str, hook: Option<&str>) -> Result<(), String> {
+    let path = path.to_string();
+    let hook = hook.map(str::to_string);
+    tokio::task::spawn_blocking(move || {
+        if let Some(hook) = &hook {
+            run_pre_delete_hook(hook, &path)?;
+        }
+        remove_with_retry(&path).map_err(|e| describe_delete_error(&e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))
+}
+
+/// Drops a deleted artifact's rows right away, so quitting mid-cleanup can't leave a ghost entry.
+pub async fn forget_deleted(pool: &sqlx::PgPool, path: &str) {
+    if let Err(e) = delete_builds_for_paths(pool, &[path.to_string()]).await {
+        log_to_file(&format!("Deleted {} but could not remove its database rows: {}", path, e));
+    }
+}
+
+pub async fn plan_cleanup(pool: &sqlx::PgPool, config: &Config) -> Result<CleanupPlan, sqlx::Error> {
+    let retention_days = config.retention_days;
+    let old_paths = get_old_artifact_paths(pool, retention_days).await?;
+    let mut beyond_count = match config.keep_last_n.filter(|n| *n > 0) {
+        Some(n) => get_artifacts_beyond_count(pool, n).await?,
+        None => vec![],
+    };
+    beyond_count.retain(|path| !old_paths.contains(path));
+    // Protected artifacts are never deleted and keep their history, and neither are
+    // ones modified on disk within the window when access-time retention is on
+    let protected_paths = config.protected_paths.clone();
+    let uses_access_time = config.retention_uses_access_time;
+    // Checking for recent changes walks each tree, so keep it off the async workers
+    let (kept, old_paths, beyond_count) = tokio::task::spawn_blocking(move || {
+        let is_kept = |p: &String| is_path_protected(p, &protected_paths) || (uses_access_time && used_within_days(p, retention_days));
+        let (mut kept, old_paths): (Vec<String>, Vec<String>) = old_paths.into_iter().partition(is_kept);
+        let (kept_beyond, beyond_count): (Vec<String>, Vec<String>) = beyond_count.into_iter().partition(is_kept);
+        kept.extend(kept_beyond);
+        (kept, old_paths, beyond_count)
+    })
+    .await
+    .unwrap_or_default();
+    Ok(CleanupPlan { expired: old_paths, beyond_count, kept })
+}
+
+pub fn cleanup_summary(bytes: u64, artifacts: usize) -> String {
+    let noun = if artifacts == 1 { "artifact" } else { "artifacts" };
+    format!("ratifact freed {} across {} {}", format_bytes(bytes), artifacts, noun)
+}
@@ -1,19 +1,50 @@
 // Build logging functionality
 
-use crate::db::connection::establish_connection;
-use crate::db::schema::create_tables;
+use crate::db::connection::{PoolSettings, establish_connection};
+use crate::db::schema::{create_tables, set_build_dedupe};
 use sqlx::PgPool;
 
+// With dedupe on, a rescan refreshes the artifact's row and remembers the size it replaced
+const UPSERT_CLAUSE: &str = " ON CONFLICT (artifact_path) DO UPDATE SET
+    project_path = EXCLUDED.project_path,
+    language = EXCLUDED.language,
+    build_time = NOW(),
+    previous_size_bytes = builds.size_bytes,
+    size_bytes = EXCLUDED.size_bytes";
+
+// Rows per INSERT; keeps the bound arrays a reasonable size on huge trees
+const BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildRecord {
+    pub project_path: String,
+    pub language: String,
+    pub artifact_path: String,
+    pub size: u64,
+}
+
 #[derive(Clone)]
 pub struct BuildLogger {
     pub pool: PgPool,
+    dedupe: bool,
 }
 
 impl BuildLogger {
-    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
-        let pool = establish_connection(database_url).await?;
+    pub async fn new(database_url: &str, settings: &PoolSettings) -> Result<Self, sqlx::Error> {
+        let pool = establish_connection(database_url, settings).await?;
         create_tables(&pool).await?;
-        Ok(BuildLogger { pool })
+        Ok(BuildLogger { pool, dedupe: false })
+    }
+
+    /// Turns upserting on `artifact_path` on or off; enabling it collapses existing duplicate rows.
+    pub async fn set_dedupe(&mut self, enabled: bool) -> Result<(), sqlx::Error> {
+        set_build_dedupe(&self.pool, enabled).await?;
+        self.dedupe = enabled;
+        Ok(())
+    }
+
+    fn insert_sql(&self, insert: &str) -> String {
+        if self.dedupe { format!("{}{}", insert, UPSERT_CLAUSE) } else { insert.to_string() }
     }
 
     pub async fn log_build(
@@ -23,15 +54,64 @@ impl BuildLogger {
         artifact_path: &str,
         size: u64,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query(
-            "INSERT INTO builds (project_path, language, artifact_path, size_bytes) VALUES ($1, $2, $3, $4)",
-        )
-        .bind(project_path)
-        .bind(language)
-        .bind(artifact_path)
-        .bind(size as i64)
-        .execute(&self.pool)
-        .await?;
+        // Carry the artifact's note over to the new row so it survives rescans
+        let sql = self.insert_sql(
+            "INSERT INTO builds (project_path, language, artifact_path, size_bytes, notes)
+             VALUES ($1, $2, $3, $4, (SELECT notes FROM builds WHERE artifact_path = $3 AND notes IS NOT NULL ORDER BY build_time DESC LIMIT 1))",
+        );
+        sqlx::query(&sql)
+            .bind(project_path)
+            .bind(language)
+            .bind(artifact_path)
+            .bind(size as i64)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("INSERT INTO size_history (artifact_path, size_bytes) VALUES ($1, $2)")
+            .bind(artifact_path)
+            .bind(size as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Logs a whole scan's artifacts with one multi-row INSERT per `BATCH_SIZE` records.
+    pub async fn log_builds_batch(&self, records: &[BuildRecord]) -> Result<(), sqlx::Error> {
+        for chunk in records.chunks(BATCH_SIZE) {
+            let project_paths: Vec<&str> = chunk.iter().map(|r| r.project_path.as_str()).collect();
+            let languages: Vec<&str> = chunk.iter().map(|r| r.language.as_str()).collect();
+            let artifact_paths: Vec<&str> = chunk.iter().map(|r| r.artifact_path.as_str()).collect();
+            let sizes: Vec<i64> = chunk.iter().map(|r| r.size as i64).collect();
+            // Same note carry-over as log_build, once per row
+            let sql = self.insert_sql(
+                "INSERT INTO builds (project_path, language, artifact_path, size_bytes, notes)
+                 SELECT b.project_path, b.language, b.artifact_path, b.size_bytes,
+                        (SELECT n.notes FROM builds n WHERE n.artifact_path = b.artifact_path AND n.notes IS NOT NULL ORDER BY n.build_time DESC LIMIT 1)
+                 FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::BIGINT[]) AS b(project_path, language, artifact_path, size_bytes)",
+            );
+            sqlx::query(&sql)
+                .bind(project_paths)
+                .bind(languages)
+                .bind(&artifact_paths)
+                .bind(&sizes)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("INSERT INTO size_history (artifact_path, size_bytes) SELECT * FROM UNNEST($1::TEXT[], $2::BIGINT[])")
+                .bind(&artifact_paths)
+                .bind(&sizes)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn set_note(&self, artifact_path: &str, note: &str) -> Result<(), sqlx::Error> {
+        // An empty note removes it
+        let note = Some(note.trim()).filter(|n| !n.is_empty());
+        sqlx::query("UPDATE builds SET notes = $2 WHERE artifact_path = $1")
+            .bind(artifact_path)
+            .bind(note)
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 }
@@ -1,5 +1,8 @@
 // Build tracking module
 // Monitors file system changes and logs builds
 
+pub mod cleanup;
 pub mod logger;
+pub mod scan;
+pub mod scanner;
 pub mod watcher;
@@ -0,0 +1,238 @@
+// Scanning the configured paths for artifacts, shared by the TUI and the daemon
+
+use crate::config::Config;
+use crate::db::schema::record_scan_run;
+use crate::tracking::logger::{BuildLogger, BuildRecord};
+use crate::tracking::scanner::{ARTIFACT_DIRS, ArtifactHit, SCAN_DEPTH, WalkOptions, find_artifacts_with_excluded};
+use crate::tracking::watcher::{BuildWatcher, WATCH_LIMIT_MESSAGE, is_watch_limit_error};
+use crate::utils::docker::docker_disk_usage;
+use crate::utils::logger::log_to_file;
+use crate::utils::{LanguageCache, calculate_dir_size, describe_walk_error, is_orphaned, format_scan_duration, is_path_excluded, normalize_path, path_from_string};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+const MAX_CONCURRENT_SCANS: usize = 4;
+
+pub struct ScanReport {
+    pub generation: u64,
+    pub artifacts: Vec<String>,
+    pub origins: HashMap<String, String>,
+    pub orphaned: Vec<String>,
+    pub virtualenvs: Vec<String>,
+    pub skipped: usize,
+    /// Scan paths that didn't exist, so "0 artifacts" isn't mistaken for a clean disk.
+    pub missing_paths: Vec<String>,
+    /// Wall time of the whole scan, all roots and Docker included.
+    pub duration: Duration,
+    /// Artifacts the exclusion list kept out, for the Show Excluded toggle.
+    pub excluded: Vec<String>,
+    /// Size of every artifact as this scan measured and recorded it.
+    pub sizes: HashMap<String, u64>,
+}
+
+/// One scan of every scan path, shared by the TUI and `ratifact daemon`.
+pub struct ScanJob {
+    scan_paths: Vec<String>,
+    excluded_paths: Vec<String>,
+    walk_options: WalkOptions,
+    clean_docker: bool,
+    logger: BuildLogger,
+    watcher: Option<BuildWatcher>,
+    watcher_enabled: Arc<AtomicBool>,
+    language_cache: Arc<Mutex<LanguageCache>>,
+}
+
+impl ScanJob {
+    /// Without a watcher the artifacts found aren't watched, as in headless runs.
+    pub fn new(config: &Config, logger: BuildLogger, watcher: Option<BuildWatcher>, language_cache: Arc<Mutex<LanguageCache>>) -> Self {
+        ScanJob {
+            scan_paths: if config.scan_paths.is_empty() { vec![".".to_string()] } else { config.scan_paths.clone() },
+            // Normalize once so older, non-canonical entries still match
+            excluded_paths: config.excluded_paths.iter().map(|p| normalize_path(p)).collect(),
+            walk_options: WalkOptions {
+                skip_hidden: config.skip_hidden,
+                follow_symlinks: config.follow_symlinks,
+                detect_cachedir_tag: config.detect_cachedir_tag,
+            },
+            clean_docker: config.clean_docker,
+            logger,
+            watcher_enabled: Arc::new(AtomicBool::new(config.watcher_enabled && watcher.is_some())),
+            watcher,
+            language_cache,
+        }
+    }
+
+    /// Walks the scan paths (a few at a time), records every artifact and the run's duration.
+    /// The report's generation is left at 0 for the caller to set.
+    pub async fn run(self, logs: Arc<Mutex<Vec<String>>>) -> ScanReport {
+        let started = Instant::now();
+        logs.lock().unwrap().push("Starting scan...".to_string());
+        let excluded_paths = Arc::new(self.excluded_paths);
+        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_SCANS));
+        let mut roots = JoinSet::new();
+        for (index, scan_path) in self.scan_paths.into_iter().enumerate() {
+            let semaphore = Arc::clone(&semaphore);
+            let excluded_paths = Arc::clone(&excluded_paths);
+            let logger = self.logger.clone();
+            let watcher = self.watcher.clone();
+            let watcher_enabled = Arc::clone(&self.watcher_enabled);
+            let language_cache = Arc::clone(&self.language_cache);
+            let logs = Arc::clone(&logs);
+            let walk_options = self.walk_options;
+            roots.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                logs.lock().unwrap().push(format!("Scanning path: {}", scan_path));
+                let (report, root_logs) =
+                    scan_root(scan_path, excluded_paths, walk_options, logger, watcher, watcher_enabled, language_cache).await;
+                // Keep each path's lines together instead of interleaving them with other roots
+                logs.lock().unwrap().extend(root_logs);
+                (index, report)
+            });
+        }
+        let mut reports = vec![];
+        while let Some(result) = roots.join_next().await {
+            if let Ok(report) = result {
+                reports.push(report);
+            }
+        }
+        // Merge in scan_paths order so the list doesn't shuffle between scans
+        reports.sort_by_key(|(index, _)| *index);
+        let mut merged = ScanReport {
+            generation: 0,
+            artifacts: vec![],
+            origins: HashMap::new(),
+            orphaned: vec![],
+            virtualenvs: vec![],
+            skipped: 0,
+            missing_paths: vec![],
+            duration: Duration::ZERO,
+            excluded: vec![],
+            sizes: HashMap::new(),
+        };
+        for (_, report) in reports {
+            merged.artifacts.extend(report.artifacts);
+            merged.origins.extend(report.origins);
+            merged.orphaned.extend(report.orphaned);
+            merged.virtualenvs.extend(report.virtualenvs);
+            merged.skipped += report.skipped;
+            merged.missing_paths.extend(report.missing_paths);
+            merged.excluded.extend(report.excluded);
+            merged.sizes.extend(report.sizes);
+        }
+        if self.clean_docker {
+            // Shells out to the docker CLI, which can hang on a slow daemon
+            let usage = tokio::task::spawn_blocking(docker_disk_usage).await.unwrap_or_default();
+            for (path, size) in usage {
+                if is_path_excluded(&path, &excluded_paths) {
+                    continue;
+                }
+                // Not a directory, so not logged to builds where retention and history would treat it as one
+                logs.lock().unwrap().push(format!("Docker: {} ({}MB reclaimable)", path, size / 1_000_000));
+                merged.sizes.insert(path.clone(), size);
+                merged.artifacts.push(path);
+            }
+        }
+        let total_count = merged.artifacts.len();
+        merged.duration = started.elapsed();
+        if let Err(e) = record_scan_run(&self.logger.pool, merged.duration, total_count).await {
+            log_to_file(&format!("Failed to record scan duration: {}", e));
+        }
+        logs.lock().unwrap().push(format!("Total scan complete in {}. Found {} artifacts.", format_scan_duration(merged.duration), total_count));
+        merged
+    }
+}
+
+async fn scan_root(
+    scan_path: String,
+    excluded_paths: Arc<Vec<String>>,
+    walk_options: WalkOptions,
+    logger: BuildLogger,
+    mut watcher: Option<BuildWatcher>,
+    watcher_enabled: Arc<AtomicBool>,
+    language_cache: Arc<Mutex<LanguageCache>>,
+) -> (ScanReport, Vec<String>) {
+    let mut logs = vec![];
+    // The generation and duration are set on the merged report
+    let mut report = ScanReport {
+        generation: 0,
+        artifacts: vec![],
+        origins: HashMap::new(),
+        orphaned: vec![],
+        virtualenvs: vec![],
+        skipped: 0,
+        missing_paths: vec![],
+        duration: Duration::ZERO,
+        excluded: vec![],
+        sizes: HashMap::new(),
+    };
+    // WalkDir yields nothing for a missing root, which would look exactly like a clean one
+    if !Path::new(&scan_path).exists() {
+        let message = format!("Path not found: {}", scan_path);
+        log_to_file(&message);
+        logs.push(message);
+        report.missing_paths.push(scan_path);
+        return (report, logs);
+    }
+    let origin = normalize_path(&scan_path);
+    // Walking and sizing block on the filesystem, so keep them off the async workers
+    let root = scan_path.clone();
+    let walked = tokio::task::spawn_blocking(move || {
+        let mut walk_errors = vec![];
+        let (hits, excluded) = find_artifacts_with_excluded(&root, SCAN_DEPTH, &ARTIFACT_DIRS, &excluded_paths, walk_options, |e| {
+            walk_errors.push(describe_walk_error(e));
+        });
+        let sized: Vec<(ArtifactHit, u64)> = hits
+            .into_iter()
+            .map(|hit| {
+                let size = calculate_dir_size(&hit.path);
+                (hit, size)
+            })
+            .collect();
+        // Excluded matches are only listed (Show Excluded), never sized, recorded or watched
+        let excluded: Vec<String> = excluded.into_iter().map(|hit| hit.path).collect();
+        (sized, excluded, walk_errors)
+    })
+    .await;
+    let (hits, excluded, walk_errors) = walked.unwrap_or_default();
+    report.excluded = excluded;
+    report.skipped = walk_errors.len();
+    logs.extend(walk_errors);
+    let mut records = Vec::with_capacity(hits.len());
+    for (ArtifactHit { path: path_str, project_path, virtualenv }, size) in hits {
+        if virtualenv {
+            report.virtualenvs.push(path_str.clone());
+        }
+        let language = language_cache.lock().unwrap().detect(&project_path);
+        if is_orphaned(&project_path) {
+            report.orphaned.push(path_str.clone());
+        }
+        records.push(BuildRecord { project_path, language, artifact_path: path_str.clone(), size });
+        // Start watching
+        if let Some(watcher) = watcher.as_mut()
+            && watcher_enabled.load(Ordering::SeqCst)
+            && let Err(e) = watcher.watch(path_from_string(&path_str))
+            && is_watch_limit_error(&e)
+            && watcher_enabled.swap(false, Ordering::SeqCst)
+        {
+            // Every further watch would fail the same way, so stop trying for this scan
+            logs.push(WATCH_LIMIT_MESSAGE.to_string());
+            log_to_file(WATCH_LIMIT_MESSAGE);
+        }
+        report.origins.insert(path_str.clone(), origin.clone());
+        report.sizes.insert(path_str.clone(), size);
+        report.artifacts.push(path_str);
+    }
+    // One round trip for the whole root instead of one per artifact
+    if let Err(e) = logger.log_builds_batch(&records).await {
+        let message = format!("Could not record {} artifacts from {}: {}", records.len(), scan_path, e);
+        log_to_file(&message);
+        logs.push(message);
+    }
+    logs.push(format!("Scan complete for {}. Found {} artifacts.", scan_path, report.artifacts.len()));
+    (report, logs)
+}
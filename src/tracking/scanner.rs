@@ -0,0 +1,172 @@
+// Artifact discovery
+// Walks scan roots and reports artifact directories; sizing, logging and watching are left to the caller
+
+use crate::utils::logger::log_to_file;
+use crate::utils::{has_cachedir_tag, is_path_excluded, is_skipped_hidden_dir, is_virtualenv, normalize_path, path_from_string, path_to_string};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Per-project exclusions in gitignore syntax, applying to the directory holding the file and below.
+pub const IGNORE_FILE: &str = ".ratifactignore";
+
+/// How deep below each scan path artifact directories are looked for.
+pub const SCAN_DEPTH: usize = 3;
+
+pub const ARTIFACT_DIRS: [&str; 21] = [
+    // Rust
+    "target",
+    // C/C++
+    "build",
+    ".build",
+    "cmake-build-debug",
+    "cmake-build-release",
+    "Debug",
+    "Release",
+    // JavaScript/TypeScript
+    "node_modules",
+    "dist",
+    ".next",
+    ".parcel-cache",
+    ".cache",
+    // Python
+    "__pycache__",
+    ".eggs",
+    "eggs",
+    // Java/Gradle
+    ".gradle",
+    // PHP/Composer
+    "vendor",
+    // Ruby
+    ".bundle",
+    // General build outputs
+    "out",
+    ".output",
+    ".nyc_output",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WalkOptions {
+    /// Don't descend into dot directories other than known artifact dirs.
+    pub skip_hidden: bool,
+    pub follow_symlinks: bool,
+    /// Also treat directories holding a CACHEDIR.TAG as artifacts, whatever their name.
+    pub detect_cachedir_tag: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactHit {
+    pub path: String,
+    pub project_path: String,
+    pub virtualenv: bool,
+}
+
+#[cfg(test)]
+pub fn find_artifacts(
+    root: &str,
+    depth: usize,
+    common_dirs: &[&str],
+    excluded: &[String],
+    options: WalkOptions,
+    on_error: impl FnMut(&walkdir::Error),
+) -> Vec<ArtifactHit> {
+    find_artifacts_with_excluded(root, depth, common_dirs, excluded, options, on_error).0
+}
+
+/// Like `find_artifacts`, but also returns the hits the exclusion list or a `.ratifactignore` filtered out.
+pub fn find_artifacts_with_excluded(
+    root: &str,
+    depth: usize,
+    common_dirs: &[&str],
+    excluded: &[String],
+    options: WalkOptions,
+    mut on_error: impl FnMut(&walkdir::Error),
+) -> (Vec<ArtifactHit>, Vec<ArtifactHit>) {
+    let WalkOptions { skip_hidden, follow_symlinks, detect_cachedir_tag } = options;
+    let scan_root = normalize_path(root);
+    // With links followed, the same tree can be reached twice (or in a cycle); walk each real directory once
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    // Prune before descending so hidden trees like .git are never walked
+    let walker = WalkDir::new(path_from_string(&scan_root)).max_depth(depth).follow_links(follow_symlinks).into_iter().filter_entry(|e| {
+        if skip_hidden
+            && e.depth() > 0
+            && e.file_type().is_dir()
+            && is_skipped_hidden_dir(&e.file_name().to_string_lossy(), common_dirs)
+            && !is_virtualenv(e.path())
+            && !(detect_cachedir_tag && has_cachedir_tag(e.path()))
+        {
+            return false;
+        }
+        if follow_symlinks && e.file_type().is_dir() {
+            return std::fs::canonicalize(e.path()).map(|real| visited.insert(real)).unwrap_or(true);
+        }
+        true
+    });
+    let mut hits = vec![];
+    let mut excluded_hits = vec![];
+    // Directories are visited before their contents, so a project's ignore file is loaded before its artifacts
+    let mut ignore_files: HashMap<PathBuf, Gitignore> = HashMap::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                on_error(&e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        if let Some(ignore) = load_ignore_file(entry.path()) {
+            ignore_files.insert(entry.path().to_path_buf(), ignore);
+        }
+        let virtualenv = is_virtualenv(entry.path());
+        let named = common_dirs.contains(&entry.file_name().to_string_lossy().as_ref());
+        // Reading CACHEDIR.TAG costs an open() per directory, so only when asked and the name didn't match
+        let tagged = || detect_cachedir_tag && has_cachedir_tag(entry.path());
+        if !(virtualenv || named || tagged()) {
+            continue;
+        }
+        // Kept lossless so a non-UTF-8 name can still be sized and deleted later
+        let path = path_to_string(entry.path());
+        let project_path = path_to_string(entry.path().parent().unwrap_or(Path::new(".")));
+        if is_path_excluded(&path, excluded) || is_ignored(entry.path(), &ignore_files) {
+            excluded_hits.push(ArtifactHit { path, project_path, virtualenv });
+        } else {
+            hits.push(ArtifactHit { path, project_path, virtualenv });
+        }
+    }
+    (hits, excluded_hits)
+}
+
+fn load_ignore_file(dir: &Path) -> Option<Gitignore> {
+    let file = dir.join(IGNORE_FILE);
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(e) = builder.add(&file) {
+        log_to_file(&format!("Problem in {}: {}", file.display(), e));
+    }
+    match builder.build() {
+        Ok(ignore) => Some(ignore),
+        Err(e) => {
+            log_to_file(&format!("Ignoring {}: {}", file.display(), e));
+            None
+        }
+    }
+}
+
+fn is_ignored(path: &Path, ignore_files: &HashMap<PathBuf, Gitignore>) -> bool {
+    // As with nested .gitignore files, the closest file that says anything about the path decides
+    for dir in path.ancestors().skip(1) {
+        if let Some(ignore) = ignore_files.get(dir) {
+            let verdict = ignore.matched_path_or_any_parents(path, true);
+            if !verdict.is_none() {
+                return verdict.is_ignore();
+            }
+        }
+    }
+    false
+}
@@ -1,30 +1,68 @@
 // File system watcher for build artifacts
 
 use notify::{RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher};
-use std::sync::{Arc, Mutex};
-use std::path::Path;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::path::{Path, PathBuf};
 use crate::utils::logger::log_to_file;
 
+pub const WATCH_LIMIT_MESSAGE: &str =
+    "Watch limit reached; live updates disabled — raise fs.inotify.max_user_watches";
+
+pub fn is_watch_limit_error(error: &notify::Error) -> bool {
+    // inotify reports ENOSPC when fs.inotify.max_user_watches is exhausted
+    match &error.kind {
+        notify::ErrorKind::MaxFilesWatch => true,
+        notify::ErrorKind::Io(e) => e.raw_os_error() == Some(28),
+        _ => false,
+    }
+}
+
 #[derive(Clone)]
 pub struct BuildWatcher {
-    watcher: Arc<Mutex<RecommendedWatcher>>,
+    // None when the platform watcher could not be created (e.g. inotify instances exhausted)
+    watcher: Option<Arc<Mutex<RecommendedWatcher>>>,
+    init_error: Option<String>,
+    // Lock order: `watched`, then `watcher`, then `roots`. watch() and unwatch_all() hold
+    // `watched` across the whole update, so taking them the other way round would deadlock.
+    watched: Arc<Mutex<HashSet<PathBuf>>>,
+    // Copy of `watched` for the event callback, updated only after `watch()` returns. notify's
+    // inotify `watch()` waits for its event thread, so that thread must never wait on `watched`.
+    roots: Arc<RwLock<HashSet<PathBuf>>>,
+    // Watched artifacts with a create/modify/remove event since the last reset_changes
+    changed: Arc<Mutex<HashSet<PathBuf>>>,
+    limit_reached: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatcherHealth {
+    Watching(usize),
+    LimitReached(usize),
+    Failed(String),
 }
 
 impl BuildWatcher {
     pub fn new(debug_logs_enabled: bool) -> Self {
+        let watched = Arc::new(Mutex::new(HashSet::new()));
+        let roots = Arc::new(RwLock::new(HashSet::new()));
+        let changed = Arc::new(Mutex::new(HashSet::new()));
+        let (event_roots, event_changed) = (Arc::clone(&roots), Arc::clone(&changed));
         let watcher = RecommendedWatcher::new(
             move |res: Result<notify::Event, notify::Error>| match res {
                 Ok(event) => {
+                    // Attribute-only changes include access times, which the size walk itself causes
+                    let is_change = matches!(
+                        event.kind,
+                        notify::EventKind::Create(_) | notify::EventKind::Modify(_) | notify::EventKind::Remove(_)
+                    ) && !matches!(event.kind, notify::EventKind::Modify(notify::event::ModifyKind::Metadata(_)));
+                    if !is_change {
+                        return;
+                    }
                     if debug_logs_enabled {
-                        if matches!(
-                            event.kind,
-                            notify::EventKind::Create(_)
-                                | notify::EventKind::Modify(_)
-                                | notify::EventKind::Remove(_)
-                        ) {
-                            log_to_file(&format!("Build change detected: {:?}", event));
-                        }
+                        log_to_file(&format!("Build change detected: {:?}", event));
                     }
+                    record_changes(&event.paths, &event_roots.read().unwrap(), &mut event_changed.lock().unwrap());
                 }
                 Err(e) => {
                     if debug_logs_enabled {
@@ -33,14 +71,86 @@ impl BuildWatcher {
                 }
             },
             notify::Config::default(),
-        )
-        .unwrap();
-        BuildWatcher { watcher: Arc::new(Mutex::new(watcher)) }
+        );
+        let (watcher, init_error) = match watcher {
+            Ok(watcher) => (Some(Arc::new(Mutex::new(watcher))), None),
+            Err(e) => {
+                log_to_file(&format!("File watcher unavailable: {}", e));
+                (None, Some(e.to_string()))
+            }
+        };
+        BuildWatcher {
+            watcher,
+            init_error,
+            watched,
+            roots,
+            changed,
+            limit_reached: Arc::new(AtomicBool::new(false)),
+        }
     }
 
     pub fn watch<P: AsRef<Path>>(&mut self, path: P) -> NotifyResult<()> {
-        self.watcher.lock().unwrap()
-            .watch(path.as_ref(), RecursiveMode::Recursive)?;
+        // Rescans hand us the same paths again; only register each one once
+        let path = path.as_ref().to_path_buf();
+        let mut watched = self.watched.lock().unwrap();
+        if watched.contains(&path) {
+            return Ok(());
+        }
+        let Some(watcher) = &self.watcher else {
+            return Err(notify::Error::generic("file watcher failed to start"));
+        };
+        if let Err(e) = watcher.lock().unwrap().watch(&path, RecursiveMode::Recursive) {
+            if is_watch_limit_error(&e) {
+                self.limit_reached.store(true, Ordering::SeqCst);
+            }
+            return Err(e);
+        }
+        self.roots.write().unwrap().insert(path.clone());
+        watched.insert(path);
         Ok(())
     }
+
+    pub fn unwatch_all(&mut self) {
+        self.limit_reached.store(false, Ordering::SeqCst);
+        let mut watched = self.watched.lock().unwrap();
+        self.roots.write().unwrap().clear();
+        let Some(watcher) = &self.watcher else {
+            watched.clear();
+            return;
+        };
+        let mut watcher = watcher.lock().unwrap();
+        for path in watched.drain() {
+            let _ = watcher.unwatch(&path);
+        }
+    }
+
+    pub fn health(&self) -> WatcherHealth {
+        match &self.init_error {
+            Some(e) => WatcherHealth::Failed(e.clone()),
+            None if self.limit_reached.load(Ordering::SeqCst) => WatcherHealth::LimitReached(self.watch_count()),
+            None => WatcherHealth::Watching(self.watch_count()),
+        }
+    }
+
+    pub fn watch_count(&self) -> usize {
+        self.watched.lock().unwrap().len()
+    }
+
+    /// Watched artifacts that changed on disk since the last `reset_changes`.
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        self.changed.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn reset_changes(&self) {
+        self.changed.lock().unwrap().clear();
+    }
+}
+
+/// Adds the watched roots the event paths fall under to `changed`.
+pub fn record_changes(paths: &[PathBuf], watched: &HashSet<PathBuf>, changed: &mut HashSet<PathBuf>) {
+    for path in paths {
+        if let Some(root) = watched.iter().find(|root| path.starts_with(root)) {
+            changed.insert(root.clone());
+        }
+    }
 }
@@ -3,25 +3,138 @@
 use ratatui::{
     Frame, Terminal,
     backend::Backend,
-    crossterm::event::{self, Event, KeyCode, KeyModifiers},
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, List, ListItem, ListState, Padding, Paragraph},
 };
-use std::time::Duration;
-use crate::config::settings::{load_config, save_config};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime};
+use crate::config::paths::config_file_path;
+use crate::config::settings::{config_parse_error, load_config, save_config};
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
-use crate::tracking::logger::BuildLogger;
-use crate::tracking::watcher::BuildWatcher;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::future::Future;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinSet;
+use crate::db::connection::{PoolSettings, connection_error_hint, ping};
+use crate::db::schema::{AgeBucket, SCAN_RUNS_SHOWN, delete_builds_for_paths, fetch_age_buckets, fetch_artifact_paths, fetch_known_artifacts, fetch_scan_durations, fetch_size_trends};
+use crate::tracking::cleanup::{ACTIVE_WITHIN_DAYS, forget_deleted, plan_cleanup, run_automatic_removal};
+use crate::tracking::logger::{BuildLogger, BuildRecord};
+use crate::tracking::scan::{ScanJob, ScanReport};
+use crate::utils::logger::log_to_file;
+use crate::tracking::watcher::{BuildWatcher, WATCH_LIMIT_MESSAGE, WatcherHealth, is_watch_limit_error};
 use crate::config::Config;
-use sqlx::{Row, types::chrono::{DateTime, Utc}};
+use sqlx::{PgPool, Row, types::chrono::{DateTime, Utc}};
 use std::io;
-use std::path::Path;
-use walkdir::WalkDir;
-use crate::utils::{detect_language_for_path, calculate_dir_size};
-use crate::ui::popup::{PopupState, PopupCommand};
+use std::path::{Path, PathBuf};
+use crate::utils::{LanguageCache, calculate_dir_size, format_bytes, format_scan_duration, format_relative_time, is_orphaned, normalize_path, is_path_excluded, is_path_protected, is_virtualenv, type_exclusion, area_exclusion, largest_files, LARGEST_FILES_WALK_LIMIT, subdirectory_sizes, truncate_middle, content_modified, modified_within_days, vanished_artifacts, path_from_string, path_to_string, display_path, rebuild_command, npm_build_scripts, free_space_bytes, free_space_plan, least_recently_modified, dependency_fingerprint, duplicate_groups};
+use crate::utils::deletion::{can_prompt_for_password, delete_path, describe_delete_error, run_pre_delete_hook, write_cleanup_script};
+use crate::utils::report::save_scan_report;
+use crate::utils::docker::{is_docker_artifact, prune_docker_artifact};
+use crate::ui::popup::{PopupState, PopupCommand, expand_size_node};
+use crate::ui::tasks::{BackgroundTasks, TaskKind};
+use crate::ui::keymap::{Action, KeyMap};
+use crate::ui::palette::Palette;
+use crate::ui::undo::UndoHistory;
+use crate::cli::{CliArgs, no_color_env, version_info};
+
+pub struct RebuildOutcome {
+    pub artifact: String,
+    pub command: &'static str,
+    pub result: io::Result<std::process::Output>,
+    pub elapsed: Duration,
+}
+
+pub struct RefreshOutcome {
+    pub refreshed: usize,
+    /// Artifacts gone from disk whose records were dropped.
+    pub removed: Vec<String>,
+    pub error: Option<String>,
+    pub elapsed: Duration,
+}
+
+pub struct DeletionOutcome {
+    pub path: String,
+    pub result: io::Result<()>,
+    pub escalated: bool,
+    /// A directory inside an artifact, which stays listed and recorded.
+    pub subdirectory: bool,
+}
+
+pub struct ClearAllOutcome {
+    /// Deleted from disk, their database rows already dropped.
+    pub removed: Vec<String>,
+    pub failed: Vec<(String, String)>,
+    /// Artifacts the pre_delete_hook kept, with its reason.
+    pub vetoed: Vec<(String, String)>,
+    pub marked: bool,
+    /// A retry of earlier failures (with a password, if one was given).
+    pub retry: bool,
+}
+
+/// A directory's subdirectories sized off the UI thread, for the subdirectory and size tree popups.
+pub struct ChildSizesOutcome {
+    pub path: String,
+    /// Only measured for a root whose size wasn't cached.
+    pub size: Option<u64>,
+    pub children: Vec<(String, u64)>,
+}
+
+/// Set by the panic hook when a thread other than the UI's panics; the main loop then quits.
+pub static BACKGROUND_PANICKED: AtomicBool = AtomicBool::new(false);
+
+pub struct LargestFilesOutcome {
+    pub path: String,
+    pub files: Vec<(String, u64)>,
+    pub truncated: bool,
+}
+
+/// The config lists that undo/redo can restore.
+#[derive(Debug, Clone, PartialEq)]
+struct ListSnapshot {
+    excluded_paths: Vec<String>,
+    protected_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ArtifactFilter {
+    #[default]
+    All,
+    Orphaned,
+    /// What automatic removal would delete on its next run
+    Expired,
+    /// Orphaned or unused past retention, and neither active nor protected
+    Safe,
+    /// Changed since the last scan or touched within ACTIVE_WITHIN_DAYS
+    Active,
+}
+
+/// The high-confidence deletion candidates the Safe category lists.
+pub fn is_safe_to_delete(orphaned: bool, stale: bool, active: bool, protected: bool) -> bool {
+    (orphaned || stale) && !active && !protected
+}
+
+// Upper bound on events handled before the next redraw, so a flood of input can't starve the UI
+const MAX_EVENTS_PER_FRAME: usize = 256;
+// Directories sized at once by Refresh Sizes; more mostly makes the disk seek
+const MAX_CONCURRENT_SIZINGS: usize = 8;
+// Room for a result from every scan that can overlap, so a finished scan never waits on the UI
+const SCAN_RESULT_CAPACITY: usize = 8;
+const COMPACT_MIN_WIDTH: u16 = 100;
+const COMPACT_MIN_HEIGHT: u16 = 24;
+const FOOTER_MIN_HEIGHT: u16 = 20;
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+const PANEL_NAMES: [&str; 5] = ["Artifacts", "History", "Charts", "Settings", "Summary"];
+const ROOT_COLORS: [Color; 6] = [Color::Cyan, Color::Magenta, Color::Yellow, Color::Green, Color::LightBlue, Color::LightRed];
+const READ_ONLY_MESSAGE: &str = "🔒 Read-only mode: deleting, clearing and rebuilding are disabled.\nRestart without --read-only to make changes.";
+const SNOOZE_INPUT_TITLE: &str = "Snooze auto-cleanup (hours, 0 to resume)";
+const MAX_SNOOZE_HOURS: f64 = 24.0 * 7.0;
+const BUDGET_GAUGE_WIDTH: usize = 10;
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 pub struct App {
     pub should_quit: bool,
@@ -31,61 +144,222 @@ pub struct App {
     pub selected: usize,
     pub focused_panel: usize,
     pub logger: BuildLogger,
+    pub db_healthy: Arc<AtomicBool>,
+    last_health_check: Instant,
     pub build_history: Vec<String>,
+    history_rows: Vec<(String, String, DateTime<Utc>)>,
+    pub notes: HashMap<String, String>,
+    pub absolute_times: bool,
     pub total_builds: usize,
     pub chart_data: Vec<(String, u64)>,
+    // Each listed artifact's most recent size, for the size budgets
+    pub latest_sizes: Vec<(String, u64)>,
+    // Reclaimable Docker space from the last scan; never stored in the database
+    docker_sizes: Vec<(String, u64)>,
     pub chart_selected: usize,
+    pub chart_min_size: u64,
+    pub chart_by_age: bool,
+    /// Days the Charts panel looks back over; None charts the latest sizes.
+    pub chart_range: Option<u32>,
+    /// Size change of each artifact within `chart_range`.
+    pub chart_trends: HashMap<String, i64>,
+    pub age_buckets: Vec<AgeBucket>,
     pub watcher: BuildWatcher,
     pub automatic_removal: bool,
+    /// Automatic removal is skipped until then, without touching the saved setting.
+    pub cleanup_snoozed_until: Option<Instant>,
     pub config: Config,
     pub popup_state: PopupState,
     pub logs: Arc<Mutex<Vec<String>>>,
     pub pending_action: Option<String>,
     pub pending_failed_paths: Vec<String>,
-    pub scan_result_tx: mpsc::Sender<Vec<String>>,
-    pub scan_result_rx: mpsc::Receiver<Vec<String>>,
+    // The artifact the note popup was opened for; a rescan can move the selection meanwhile
+    pub note_target: Option<String>,
+    pub scan_result_tx: mpsc::Sender<ScanReport>,
+    pub scan_result_rx: mpsc::Receiver<ScanReport>,
+    scan_generation: u64,
+    pub deletion_tx: mpsc::Sender<DeletionOutcome>,
+    pub deletion_rx: mpsc::Receiver<DeletionOutcome>,
+    pub clear_all_tx: mpsc::Sender<ClearAllOutcome>,
+    pub clear_all_rx: mpsc::Receiver<ClearAllOutcome>,
+    deleting: Option<(String, Instant)>,
+    pub rebuild_tx: mpsc::Sender<RebuildOutcome>,
+    pub rebuild_rx: mpsc::Receiver<RebuildOutcome>,
+    rebuilding: Option<(&'static str, Instant)>,
+    pub refresh_tx: mpsc::Sender<RefreshOutcome>,
+    pub refresh_rx: mpsc::Receiver<RefreshOutcome>,
+    refreshing: Option<Instant>,
+    pub child_sizes_tx: mpsc::Sender<ChildSizesOutcome>,
+    pub child_sizes_rx: mpsc::Receiver<ChildSizesOutcome>,
+    pub largest_files_tx: mpsc::Sender<LargestFilesOutcome>,
+    pub largest_files_rx: mpsc::Receiver<LargestFilesOutcome>,
+    tasks: BackgroundTasks,
+    pub spinner_frame: usize,
+    pub no_color: bool,
+    pub language_cache: Arc<Mutex<LanguageCache>>,
+    pub use_language_cache: bool,
+    /// Set by --read-only: nothing on disk is deleted or rebuilt.
+    pub read_only: bool,
+    pub keymap: KeyMap,
+    pub orphaned: HashSet<String>,
+    // The last batch from the database was full, so it may hold more than is listed
+    more_artifacts: bool,
+    // Artifacts automatic removal would delete, for the Expired filter
+    expired: HashSet<String>,
+    // Members of the Safe and Active categories, worked out when the category filter is switched on
+    safe: HashSet<String>,
+    active: HashSet<String>,
+    // Matches the exclusion list hides; listed dimmed below the artifacts while show_excluded is on
+    excluded_hits: Vec<String>,
+    show_excluded: bool,
+    pub artifact_filter: ArtifactFilter,
+    pub marked: HashSet<String>,
+    /// Normalized scan path each artifact was found under.
+    pub origins: HashMap<String, String>,
+    pub virtualenvs: HashSet<String>,
+    undo_history: UndoHistory<ListSnapshot>,
+    last_scan_duration: Option<Duration>,
+    // Scan path with the least free space, refreshed with the health check
+    free_space: Option<(String, u64)>,
+    // Recorded scan durations, newest first
+    scan_durations: Vec<Duration>,
+    // Configured scan paths while a path given on the command line replaces them for this session
+    saved_scan_paths: Option<Vec<String>>,
 }
 
 impl App {
-    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let config = load_config();
-        let logger = BuildLogger::new(&config.database_url).await?;
+    pub async fn new(cli: &CliArgs) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config = load_config();
+        let saved_scan_paths =
+            cli.scan_path.as_deref().map(|path| std::mem::replace(&mut config.scan_paths, vec![normalize_path(path)]));
+        let mut config_problems: Vec<String> = config_parse_error(&config_file_path()).into_iter().collect();
+        if let Err(problems) = config.validate() {
+            config_problems.extend(problems);
+        }
+        let no_color = config.no_color || cli.no_color || no_color_env();
+        let pool_settings = PoolSettings::from(&config);
+        let mut logger = match BuildLogger::new(&config.database_url, &pool_settings).await {
+            Ok(logger) => logger,
+            Err(e) if let Some(message) = connection_error_hint(&e, &pool_settings) => return Err(message.into()),
+            // Without a database there is no UI to show the problems in, so put them in the error
+            Err(e) if !config_problems.is_empty() => {
+                return Err(format!("{}\nConfig problems:\n- {}", e, config_problems.join("\n- ")).into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if let Err(e) = logger.set_dedupe(config.dedupe_builds).await {
+            config_problems.push(format!("dedupe_builds could not be applied: {}", e));
+        }
+        let (keymap, keymap_problems) = KeyMap::from_overrides(&config.keybindings);
+        config_problems.extend(keymap_problems);
         let watcher = BuildWatcher::new(config.debug_logs_enabled);
-        let (tx, rx) = mpsc::channel(1);
+        let (tx, rx) = mpsc::channel(SCAN_RESULT_CAPACITY);
+        let (deletion_tx, deletion_rx) = mpsc::channel(1);
+        let (clear_all_tx, clear_all_rx) = mpsc::channel(1);
+        let (rebuild_tx, rebuild_rx) = mpsc::channel(1);
+        let (refresh_tx, refresh_rx) = mpsc::channel(1);
+        let (child_sizes_tx, child_sizes_rx) = mpsc::channel(1);
+        let (largest_files_tx, largest_files_rx) = mpsc::channel(1);
         let mut app = App {
             should_quit: false,
             artifacts: vec![], // Start empty
             scanning: false,
             scanned: false,
             selected: 0,
-            focused_panel: 0,
+            // Come back to the panel and removal mode used last time
+            focused_panel: config.last_focused_panel % 5,
             logger,
+            db_healthy: Arc::new(AtomicBool::new(true)),
+            last_health_check: Instant::now(),
             build_history: vec![],
+            history_rows: vec![],
+            notes: HashMap::new(),
+            absolute_times: false,
             total_builds: 0,
             chart_data: vec![],
+            latest_sizes: vec![],
+            docker_sizes: vec![],
             chart_selected: 0,
+            chart_min_size: 0,
+            chart_by_age: false,
+            chart_range: None,
+            chart_trends: HashMap::new(),
+            age_buckets: vec![],
             watcher,
-            automatic_removal: true,
+            // Read-only sessions never clean up, without touching the saved setting
+            automatic_removal: config.automatic_removal && !cli.read_only,
+            cleanup_snoozed_until: None,
+            undo_history: UndoHistory::new(config.undo_history_limit),
+            last_scan_duration: None,
+            free_space: None,
+            scan_durations: vec![],
+            saved_scan_paths,
             config,
             popup_state: PopupState::None,
             logs: Arc::new(Mutex::new(vec![])),
             pending_action: None,
             pending_failed_paths: vec![],
+            note_target: None,
             scan_result_tx: tx,
             scan_result_rx: rx,
+            scan_generation: 0,
+            deletion_tx,
+            deletion_rx,
+            clear_all_tx,
+            clear_all_rx,
+            deleting: None,
+            rebuild_tx,
+            rebuild_rx,
+            rebuilding: None,
+            refresh_tx,
+            refresh_rx,
+            refreshing: None,
+            child_sizes_tx,
+            child_sizes_rx,
+            largest_files_tx,
+            largest_files_rx,
+            tasks: BackgroundTasks::default(),
+            spinner_frame: 0,
+            no_color,
+            language_cache: Arc::new(Mutex::new(LanguageCache::default())),
+            use_language_cache: !cli.no_cache,
+            read_only: cli.read_only,
+            keymap,
+            orphaned: HashSet::new(),
+            more_artifacts: false,
+            expired: HashSet::new(),
+            safe: HashSet::new(),
+            active: HashSet::new(),
+            excluded_hits: vec![],
+            show_excluded: false,
+            artifact_filter: ArtifactFilter::All,
+            marked: HashSet::new(),
+            origins: HashMap::new(),
+            virtualenvs: HashSet::new(),
         };
+        app.reconcile_builds().await;
         app.load_artifacts().await;
         app.load_history().await;
+        app.refresh_free_space();
+        if !config_problems.is_empty() {
+            let message = format!(
+                "⚠️  Config problems:\n\n- {}\n\nFix them in {} (changing settings here rewrites that file).",
+                config_problems.join("\n- "),
+                config_file_path().display()
+            );
+            app.popup_state = PopupState::Error { message };
+        }
         Ok(app)
     }
 
     pub async fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
         loop {
+        self.spinner_frame = self.spinner_frame.wrapping_add(1);
         terminal.draw(|f| self.draw(f))?;
 
         self.handle_event().await;
 
-        if self.should_quit {
+        if self.should_quit || BACKGROUND_PANICKED.load(Ordering::SeqCst) {
                 break;
             }
         }
@@ -94,253 +368,424 @@ impl App {
 
     async fn handle_event(&mut self) {
         // Trigger automatic scan after UI is loaded
-        if !self.scanned && !self.scanning {
+        // Wait until startup popups (config problems) are dismissed so the scan popup doesn't hide them
+        if !self.scanned && !self.scanning && matches!(self.popup_state, PopupState::None) {
             self.trigger_scan().await;
         }
 
-        // Check for scan completion
-        if let Ok(artifacts) = self.scan_result_rx.try_recv() {
-            self.artifacts = artifacts;
+        if self.last_health_check.elapsed() >= HEALTH_CHECK_INTERVAL {
+            self.last_health_check = Instant::now();
+            self.refresh_free_space();
+            let pool = self.logger.pool.clone();
+            let db_healthy = Arc::clone(&self.db_healthy);
+            // Not tracked: a background ping every few seconds shouldn't flash the busy spinner
+            tokio::spawn(async move {
+                let healthy = ping(&pool, HEALTH_CHECK_TIMEOUT).await;
+                if db_healthy.swap(healthy, Ordering::SeqCst) != healthy {
+                    log_to_file(if healthy { "Database reachable again" } else { "Database health check failed" });
+                }
+            });
+        }
+
+        // Check for scan completion; results from scans superseded by a newer one are dropped
+        let mut latest = None;
+        while let Ok(report) = self.scan_result_rx.try_recv() {
+            if report.generation == self.scan_generation {
+                latest = Some(report);
+            } else {
+                log_to_file(&format!("Dropped result of superseded scan #{}", report.generation));
+            }
+        }
+        if let Some(report) = latest {
+            self.docker_sizes = report.sizes.iter().filter(|(path, _)| is_docker_artifact(path)).map(|(path, size)| (path.clone(), *size)).collect();
+            self.artifacts = report.artifacts;
+            self.origins = report.origins;
+            self.orphaned = report.orphaned.into_iter().collect();
+            self.virtualenvs = report.virtualenvs.into_iter().collect();
+            self.excluded_hits = report.excluded;
+            // A scan lists everything it finds
+            self.more_artifacts = false;
+            self.selected = 0;
             self.scanning = false;
             self.scanned = true;
-            self.popup_state = PopupState::Info { message: format!("Scan complete. Found {} artifacts.", self.artifacts.len()) };
+            self.last_scan_duration = Some(report.duration);
+            let mut message = format!("Scan complete in {}. Found {} artifacts.", format_scan_duration(report.duration), self.artifacts.len());
+            if report.skipped > 0 {
+                message.push_str(&format!("\nSkipped {} unreadable entries (see Logs).", report.skipped));
+            }
+            for path in &report.missing_paths {
+                message.push_str(&format!("\n⚠️  Path not found: {} (typo or unmounted drive?)", path));
+            }
+            self.popup_state = PopupState::Info { message };
             let _ = self.load_history().await;
+            self.write_scan_report(&report.sizes, report.duration);
+            if matches!(self.artifact_filter, ArtifactFilter::Safe | ArtifactFilter::Active) {
+                self.load_categories().await;
+            }
+            if self.config.chart_auto_select_largest
+                && let Some(largest) = index_of_largest(&self.visible_chart_data())
+            {
+                self.chart_selected = largest;
+                self.focused_panel = 2;
+            }
 
             // Trigger automatic cleanup if enabled
-            if self.automatic_removal {
+            if self.automatic_removal
+                && let Some(remaining) = self.cleanup_snooze_remaining()
+            {
+                self.logs.lock().unwrap().push(format!("Automatic removal skipped (snoozed for {} more)", format_duration_short(remaining)));
+            } else if self.automatic_removal {
                 let pool = self.logger.pool.clone();
-                let retention_days = self.config.retention_days;
-                tokio::spawn(async move {
-                    // Get old artifact paths from database
-                    match crate::db::schema::get_old_artifact_paths(&pool, retention_days).await {
-                        Ok(old_paths) => {
-                            // Delete directories from disk
-                            for path in old_paths {
-                                let _ = std::fs::remove_dir_all(&path);
-                            }
-                            // Remove entries from database
-                            let _ = crate::db::schema::delete_old_builds_from_db(&pool, retention_days).await;
-                        }
-                        Err(_) => {
-                            // Cleanup query failed, continue normally
-                        }
-                    }
+                let config = self.config.clone();
+                let logs = Arc::clone(&self.logs);
+                self.spawn_tracked("automatic removal", TaskKind::Critical, async move {
+                    run_automatic_removal(&pool, &config, &logs).await;
                 });
             }
         }
 
-        // Use non-blocking poll with timeout to allow UI to redraw
+        if let Ok(outcome) = self.deletion_rx.try_recv() {
+            self.finish_deletion(outcome).await;
+        } else if let Ok(outcome) = self.clear_all_rx.try_recv() {
+            self.finish_clear_all_builds(outcome).await;
+        } else if let Some((path, started)) = &self.deleting
+            && let PopupState::Progress { message } = &mut self.popup_state
+        {
+            *message = format!("Deleting {}... ({}s)", path, started.elapsed().as_secs());
+        }
+
+        if let Ok(outcome) = self.refresh_rx.try_recv() {
+            self.finish_refresh_sizes(outcome).await;
+        } else if let Some(started) = &self.refreshing
+            && let PopupState::Progress { message } = &mut self.popup_state
+        {
+            *message = format!("Refreshing sizes of known artifacts... ({}s)", started.elapsed().as_secs());
+        }
+
+        if let Ok(outcome) = self.child_sizes_rx.try_recv() {
+            self.finish_child_sizes(outcome);
+        }
+        if let Ok(outcome) = self.largest_files_rx.try_recv() {
+            self.finish_largest_files(outcome);
+        }
+
+        if let Ok(outcome) = self.rebuild_rx.try_recv() {
+            self.finish_rebuild(outcome);
+        } else if let Some((command, started)) = &self.rebuilding
+            && let PopupState::Progress { message } = &mut self.popup_state
+        {
+            *message = format!("Rebuilding project ({})... ({}s)", command, started.elapsed().as_secs());
+        }
+
+        // Wait briefly for input so the UI keeps redrawing, then drain everything queued since the
+        // last frame. Handling one event per frame lets held keys pile up and overshoot after release.
+        let mut events = vec![];
         if event::poll(Duration::from_millis(100)).unwrap_or(false) {
-            if let Ok(event) = event::read() {
-                match event {
-                    Event::Resize(_, _) => {
-                        // Terminal was resized, UI will redraw automatically on next loop
-                    }
-                    Event::Key(key) => {
-            // Handle popup first
-            if let Some(cmd) = self.popup_state.handle_key(&key) {
-                match cmd {
-                    PopupCommand::OpenInput { title, initial } => {
-                        let initial = if title == "Retention Days" {
-                            self.config.retention_days.to_string()
-                        } else {
-                            initial
-                        };
-                        self.popup_state = PopupState::new_input(title, initial);
-                    }
-                    PopupCommand::OpenDirBrowse => {
-                        self.popup_state = PopupState::new_dir_browse();
-                    }
-                    PopupCommand::ToggleRemoval => {
-                        if !self.automatic_removal {
-                            // Show warning when enabling automatic removal
-                            let message = "⚠️  AUTOMATIC REMOVAL WILL DELETE OLD ARTIFACTS\n\nPlease verify your build directories in the list above.\nAny directories matching common build paths older than\nretention days will be permanently deleted.\n\nEnable automatic removal? (Enter: Yes, Esc: No)".to_string();
-                            let action = "enable_automatic_removal".to_string();
-                            self.popup_state = PopupState::ConfirmAction { message, action };
-                        } else {
-                            // Disabling is safe, just toggle
-                            self.automatic_removal = false;
+            while let Ok(event) = event::read() {
+                events.push(event);
+                if events.len() >= MAX_EVENTS_PER_FRAME || !event::poll(Duration::ZERO).unwrap_or(false) {
+                    break;
+                }
+            }
+        }
+
+        // Consecutive Up/Down presses collapse into a single clamped move
+        let mut nav_delta = 0;
+        for event in events {
+            // Resize needs no handling, the UI redraws on the next loop
+            if let Event::Key(key) = event
+                && key.kind != KeyEventKind::Release
+            {
+                if let Some(delta) = self.navigation_delta(&key) {
+                    nav_delta += delta;
+                    continue;
+                }
+                self.move_selection(std::mem::take(&mut nav_delta));
+                self.handle_key(key).await;
+                if self.should_quit {
+                    return;
+                }
+            }
+        }
+        self.move_selection(nav_delta);
+    }
+
+    fn navigation_delta(&self, key: &KeyEvent) -> Option<isize> {
+        if !matches!(self.popup_state, PopupState::None) || !matches!(self.focused_panel, 0 | 2) {
+            return None;
+        }
+        let code = if self.config.vim_keys { vim_remap(key.code) } else { key.code };
+        match code {
+            KeyCode::Up | KeyCode::PageUp => Some(-1),
+            KeyCode::Down | KeyCode::PageDown => Some(1),
+            _ => None,
+        }
+    }
+
+    async fn perform(&mut self, action: Action) {
+        if self.read_only && matches!(action, Action::Delete | Action::ClearAll | Action::Rebuild) {
+            self.popup_state = PopupState::Info { message: READ_ONLY_MESSAGE.to_string() };
+            return;
+        }
+        let artifacts_panel = self.focused_panel == 0;
+        let artifact_selected = artifacts_panel && self.selected_artifact().is_some();
+        match action {
+            Action::ClearAll => {
+                self.popup_state = PopupState::new_clear_all_confirmation(self.artifact_entries());
+            },
+            Action::Quit => self.should_quit = true,
+            Action::Help => self.popup_state = PopupState::Help { text: self.help_text() },
+            Action::About => {
+                self.popup_state = PopupState::Info { message: format!("ℹ️  About\n\n{}\n\nInclude this when reporting a bug.", version_info()) };
+            },
+            Action::Scan if !self.scanning => self.trigger_scan().await,
+            Action::Delete if !self.marked_visible().is_empty() => {
+                self.popup_state = PopupState::new_clear_all_confirmation(self.artifact_entries());
+            },
+            Action::Delete => self.request_delete(),
+            Action::Mark if artifacts_panel => self.toggle_mark(),
+            Action::MarkAll if artifacts_panel => self.mark_all_visible(),
+            Action::InvertMarks if artifacts_panel => self.invert_marks(),
+            Action::Exclude if artifacts_panel && self.selected_excluded().is_some() => self.unexclude_selected().await,
+            Action::Exclude if artifact_selected => self.exclude_selected().await,
+            Action::ExcludeType if artifact_selected => self.confirm_exclude_type(),
+            Action::ExcludeArea if artifact_selected => self.confirm_exclude_area(),
+            Action::Rebuild => self.request_rebuild(),
+            Action::Note if artifact_selected => self.edit_note(),
+            Action::ToggleFooter => self.toggle_footer(),
+            Action::TogglePaths => self.toggle_absolute_paths(),
+            Action::ExportScript => {
+                self.popup_state = PopupState::new_input("Export Cleanup Script".to_string(), "ratifact-cleanup.sh".to_string());
+            },
+            Action::CopyPath if artifact_selected => self.yank_selected(),
+            Action::Protect if artifact_selected => self.toggle_protection(),
+            Action::History => self.load_history().await,
+            Action::Reconnect => self.reconnect_database().await,
+            Action::ChartAge if self.focused_panel == 2 => self.chart_by_age = !self.chart_by_age,
+            Action::ChartRange if self.focused_panel == 2 => self.cycle_chart_range().await,
+            Action::ToggleTimes if !self.history_rows.is_empty() => self.toggle_time_format(),
+            Action::Compare => self.open_scan_diff().await,
+            Action::Usage => self.open_language_usage().await,
+            Action::Duplicates => self.open_duplicates(),
+            Action::ToggleWatcher => self.toggle_watcher(),
+            Action::ToggleRemoval => self.toggle_automatic_removal(),
+            Action::Orphans => self.toggle_orphan_filter(),
+            Action::ExpiredFilter => self.toggle_expired_filter().await,
+            Action::CategoryFilter => self.cycle_category_filter().await,
+            Action::BudgetPlan => self.mark_budget_plan().await,
+            Action::ShowExcluded => self.toggle_show_excluded(),
+            Action::RefreshSizes => self.refresh_sizes(),
+            Action::LoadMore => self.load_more_artifacts().await,
+            Action::Settings => self.popup_state = PopupState::new_settings_list(self.focused_panel),
+            Action::Logs => self.popup_state = PopupState::new_logs_popup(Arc::clone(&self.logs)),
+            Action::SnoozeCleanup => {
+                self.popup_state = PopupState::new_input(SNOOZE_INPUT_TITLE.to_string(), "2".to_string());
+            },
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.focused_panel == 0 {
+            self.selected = step_index(self.selected, delta, self.artifact_row_count());
+        } else if self.focused_panel == 2 {
+            self.chart_selected = step_index(self.chart_selected, delta, self.visible_chart_data().len());
+        }
+    }
+
+    async fn handle_key(&mut self, key: KeyEvent) {
+        // Handle popup first
+        if let Some(cmd) = self.popup_state.handle_key(&key) {
+            if self.read_only && is_destructive_command(&cmd) {
+                self.popup_state = PopupState::Info { message: READ_ONLY_MESSAGE.to_string() };
+                return;
+            }
+            match cmd {
+                PopupCommand::OpenInput { title, initial } => {
+                    let initial = if title == "Retention Days" {
+                        self.config.retention_days.to_string()
+                    } else {
+                        initial
+                    };
+                    self.popup_state = PopupState::new_input(title, initial);
+                }
+                PopupCommand::OpenDirBrowse => {
+                    self.popup_state = PopupState::new_dir_browse();
+                }
+                PopupCommand::ToggleRemoval => self.toggle_automatic_removal(),
+                PopupCommand::SetValue { key, value } => {
+                    if key == "Retention Days" {
+                        if let Ok(days) = value.parse::<u32>() {
+                            self.config.retention_days = days;
                         }
-                    }
-                    PopupCommand::SetValue { key, value } => {
-                        if key == "Retention Days" {
-                            if let Ok(days) = value.parse::<u32>() {
-                                self.config.retention_days = days;
-                            }
-                        } else if key == "Scan Path" {
-                            self.config.scan_paths = vec![value];
-                         } else if key == "Enter sudo password" {
-                             if let Some(action) = self.pending_action.take() {
-                                 if action == "delete" {
-                                     let path = self.artifacts[self.selected].clone();
-                                     let password = value.clone();
-
-                                     // Try the deletion synchronously to check if it succeeds
-                                     if Self::delete_with_sudo_blocking(&path, Some(&password)) {
-                                         self.artifacts.remove(self.selected);
-                                         if self.selected >= self.artifacts.len() && self.selected > 0 {
-                                             self.selected -= 1;
-                                         }
-                                         // Update DB in background
-                                         let pool = self.logger.pool.clone();
-                                         tokio::spawn(async move {
-                                             let _ = sqlx::query("DELETE FROM builds WHERE artifact_path = $1").bind(&path).execute(&pool).await;
-                                         });
-                                         self.popup_state = PopupState::Info { message: "Artifact deleted successfully.".to_string() };
-                                     } else {
-                                         self.popup_state = PopupState::Info { message: "Deletion failed - please check permissions or try again.".to_string() };
-                                     }
-                                 } else if action == "clear_all" {
-                                     let failed_paths = self.pending_failed_paths.clone();
-                                     self.pending_failed_paths.clear();
-                                     let password = value.clone();
-
-                                     let mut all_success = true;
-                                     for path in &failed_paths {
-                                         if !Self::delete_with_sudo_blocking(path, Some(&password)) {
-                                             all_success = false;
-                                         }
-                                     }
-
-                                     if all_success {
-                                         self.artifacts.clear();
-                                         let pool = self.logger.pool.clone();
-                                         tokio::spawn(async move {
-                                             let _ = sqlx::query("DELETE FROM builds").execute(&pool).await;
-                                         });
-                                         self.popup_state = PopupState::Info { message: "All builds cleared successfully.".to_string() };
-                                     } else {
-                                         self.popup_state = PopupState::Info { message: "Some deletions failed - please check permissions.".to_string() };
-                                     }
-                                 }
+                    } else if key == "Scan Path" {
+                        // Picking a path in settings is deliberate, so it's saved even in a one-off session
+                        self.config.scan_paths = vec![value];
+                        self.saved_scan_paths = None;
+                    } else if key == "Export Cleanup Script" {
+                        self.export_cleanup_script(&value);
+                    } else if key == "Artifact Note" {
+                        self.save_note(value).await;
+                    } else if key == SNOOZE_INPUT_TITLE {
+                        self.snooze_cleanup(&value);
+                     } else if key == "Enter sudo password"
+                         && let Some(action) = self.pending_action.take() {
+                             if let Some(path) = action.strip_prefix("delete:") {
+                                 self.start_deletion(path.to_string(), Some(value.clone()), false);
+                             } else if let Some(child) = action.strip_prefix("delete_subdir:") {
+                                 self.delete_subdirectory(child.to_string(), Some(&value));
+                             } else if action == "clear_all" {
+                                 self.retry_clear_all(Some(&value));
                              }
-                        }
-                        // Save config after changes
-                        save_config(&self.config).ok();
-                    }
-                    PopupCommand::DeleteArtifact => {
-                        self.popup_state = PopupState::new_confirm_action("Delete this artifact?".to_string(), "delete".to_string());
                     }
-                    PopupCommand::RebuildArtifact => {
-                        self.popup_state = PopupState::new_confirm_action("Rebuild this project?".to_string(), "rebuild".to_string());
-                    }
-                    PopupCommand::ClearAllBuilds => {
-                        self.clear_all_builds().await;
-                    }
-                    PopupCommand::ConfirmAction { action } => {
-                         if action.starts_with("remove_excluded:") {
-                             let path = action.strip_prefix("remove_excluded:").unwrap_or("").to_string();
-                             self.config.excluded_paths.retain(|p| p != &path);
-                             save_config(&self.config).ok();
-                             self.popup_state = PopupState::Info { message: format!("Removed from exclusion list. Rescanning...", ) };
-                             if !self.scanning {
-                                 self.trigger_scan().await;
+                    // Save config after changes
+                    self.save_config();
+                }
+                PopupCommand::DeleteArtifact => {
+                    self.request_delete();
+                }
+                PopupCommand::RebuildArtifact => {
+                    self.request_rebuild();
+                }
+                PopupCommand::ClearAllBuilds => {
+                    self.clear_all_builds();
+                }
+                PopupCommand::BrowseSubdirectories => {
+                    self.open_subdirectories();
+                }
+                PopupCommand::ShowLargestFiles => {
+                    self.open_largest_files();
+                }
+                PopupCommand::ShowSizeTree => {
+                    self.open_size_tree();
+                }
+                PopupCommand::ExpandSizeNode { path } => {
+                    self.size_children(path, false);
+                }
+                PopupCommand::ConfirmAction { action } => {
+                     if let Some(child) = action.strip_prefix("delete_subdir:") {
+                         self.delete_subdirectory(child.to_string(), None);
+                     } else if let Some(artifact) = action.strip_prefix("rescan_size:") {
+                         self.rescan_size(artifact.to_string()).await;
+                     } else if let Some(entry) = action.strip_prefix("exclude_type:").or_else(|| action.strip_prefix("exclude_area:")) {
+                         self.add_exclusion(entry.to_string()).await;
+                     } else if action.starts_with("remove_excluded:") {
+                         let path = action.strip_prefix("remove_excluded:").unwrap_or("").to_string();
+                         self.record_undo(format!("remove exclusion {}", path));
+                         self.config.excluded_paths.retain(|p| p != &path);
+                         self.save_config();
+                         self.popup_state = PopupState::Info { message: "Removed from exclusion list. Rescanning...".to_string() };
+                         if !self.scanning {
+                             self.trigger_scan().await;
+                         }
+                     } else {
+                         match action.as_str() {
+                             "delete" => {
+                                 // delete_selected sets the popup_state
+                                 self.delete_selected();
                              }
-                         } else {
-                             match action.as_str() {
-                                 "delete" => {
-                                     self.popup_state = PopupState::new_progress("Deleting artifact...".to_string());
-                                     self.delete_selected().await;
-                                     // delete_selected sets the popup_state
-                                 }
-                                "rebuild" => {
-                                    self.rebuild_selected();
-                                    self.popup_state = PopupState::new_progress("Rebuilding project...".to_string());
-                                }
-                                "exclude" => {
-                                    if self.selected < self.artifacts.len() {
-                                        let path = self.artifacts[self.selected].clone();
-                                        self.config.excluded_paths.push(path);
-                                        self.artifacts.remove(self.selected);
-                                        if self.selected >= self.artifacts.len() && self.selected > 0 {
-                                            self.selected -= 1;
-                                        }
-                                        save_config(&self.config).ok();
-                                        self.popup_state = PopupState::Info { message: "Path added to exclusion list.".to_string() };
-                                    }
-                                }
-                                "enable_automatic_removal" => {
-                                    self.automatic_removal = true;
-                                    self.popup_state = PopupState::Info { message: "Automatic removal enabled. Old artifacts will be cleaned up after scans.".to_string() };
-                                }
-                                _ => {}
+                            "rebuild" => {
+                                self.rebuild_selected();
                             }
-                         }
-                    }
-                    PopupCommand::OpenExcludedPaths => {
-                        self.popup_state = PopupState::new_excluded_paths(self.config.excluded_paths.clone());
-                    }
-                }
-            } else if matches!(self.popup_state, PopupState::None) {
-                // Main keys only when no popup
-                match key.code {
-                    KeyCode::Char('D') if key.modifiers.contains(KeyModifiers::SHIFT) => {
-                        self.popup_state = PopupState::new_clear_all_confirmation();
-                    },
-                    KeyCode::Enter => {
-                        if self.focused_panel == 0 {
-                            self.popup_state = PopupState::new_artifact_actions();
-                        } else if self.focused_panel == 3 {
-                            self.popup_state = PopupState::new_settings_list();
-                        }
-                    },
-                    KeyCode::Char('q') => self.should_quit = true,
-                    KeyCode::Tab => self.focused_panel = (self.focused_panel + 1) % 5,
-                    KeyCode::Char('s') => if !self.scanning { self.trigger_scan().await; },
-                     KeyCode::Char('d') => self.popup_state = PopupState::new_confirm_action("Delete this artifact?".to_string(), "delete".to_string()),
-                    KeyCode::Char('x') | KeyCode::Char('X') => {
-                        if self.focused_panel == 0 && self.selected < self.artifacts.len() {
-                            self.popup_state = PopupState::new_confirm_action("Exclude this path from scanning?".to_string(), "exclude".to_string());
+                            "retry_clear_all" if can_prompt_for_password() => {
+                                self.pending_action = Some("clear_all".to_string());
+                                self.popup_state = PopupState::new_input("Enter sudo password".to_string(), "".to_string());
+                            }
+                            "retry_clear_all" => {
+                                self.retry_clear_all(None);
+                            }
+                            "enable_automatic_removal" => {
+                                self.set_automatic_removal(true);
+                                self.popup_state = PopupState::Info { message: "Automatic removal enabled. Old artifacts will be cleaned up after scans.".to_string() };
+                            }
+                            _ => {}
                         }
-                    },
-                    KeyCode::Char('r') => self.rebuild_selected(),
-                    KeyCode::Char('h') => self.load_history().await,
-                    KeyCode::Char('e') => self.popup_state = PopupState::new_settings_list(),
-                     KeyCode::Char('l') => self.popup_state = PopupState::new_logs_popup(Arc::clone(&self.logs)),
-                     KeyCode::Up | KeyCode::PageUp => {
-                         if self.focused_panel == 0 && self.selected > 0 {
-                             self.selected -= 1;
-                         } else if self.focused_panel == 2 && self.chart_selected > 0 {
-                             self.chart_selected -= 1;
-                         }
                      }
-                     KeyCode::Down | KeyCode::PageDown => {
-                         if self.focused_panel == 0 && self.selected < self.artifacts.len().saturating_sub(1) {
-                             self.selected += 1;
-                         } else if self.focused_panel == 2 && self.chart_selected < self.chart_data.len().saturating_sub(1) {
-                             self.chart_selected += 1;
-                         }
-                     }
-                    _ => {}
                 }
-            } else {
-                // Popup open, only allow quit
-                if key.code == KeyCode::Char('q') {
-                    self.should_quit = true;
+                PopupCommand::OpenExcludedPaths => {
+                    self.popup_state = PopupState::new_excluded_paths(self.config.excluded_paths.clone());
+                }
+                PopupCommand::ToggleVimKeys => {
+                    self.config.vim_keys = !self.config.vim_keys;
+                    self.save_config();
+                    let message = if self.config.vim_keys {
+                        "Vim keys enabled: j/k move, h/l switch panels, H: history, L: logs."
+                    } else {
+                        "Vim keys disabled."
+                    };
+                    self.popup_state = PopupState::Info { message: message.to_string() };
+                }
+                PopupCommand::ToggleDocker => {
+                    self.config.clean_docker = !self.config.clean_docker;
+                    self.save_config();
+                    let message = if self.config.clean_docker {
+                        "Docker cleanup enabled. Dangling images and build cache appear after the next scan."
+                    } else {
+                        "Docker cleanup disabled."
+                    };
+                    self.popup_state = PopupState::Info { message: message.to_string() };
                 }
             }
+        } else if matches!(self.popup_state, PopupState::None) {
+            // Main keys only when no popup
+            let code = if self.config.vim_keys { vim_remap(key.code) } else { key.code };
+            // Navigation, the chart threshold and Ctrl shortcuts are fixed; everything else goes through the key map
+            match code {
+                KeyCode::Enter => {
+                    if self.focused_panel == 0 {
+                        self.popup_state = PopupState::new_artifact_actions();
+                    } else if self.focused_panel == 3 {
+                        self.popup_state = PopupState::new_settings_list(self.focused_panel);
                     }
-                    _ => {
-                        // Ignore other events
+                },
+                KeyCode::Tab => self.focused_panel = (self.focused_panel + 1) % 5,
+                KeyCode::BackTab => self.focused_panel = (self.focused_panel + 4) % 5,
+                KeyCode::Esc if self.focused_panel == 0 => self.clear_filter_or_marks(),
+                KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => self.undo().await,
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.redo().await,
+                KeyCode::Char('+') | KeyCode::Char('=') if self.focused_panel == 2 => self.adjust_chart_threshold(true),
+                KeyCode::Char('-') if self.focused_panel == 2 => self.adjust_chart_threshold(false),
+                KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) => self.resize_focused_panel(-1, 0),
+                KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) => self.resize_focused_panel(1, 0),
+                KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => self.resize_focused_panel(0, 1),
+                KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => self.resize_focused_panel(0, -1),
+                KeyCode::Up | KeyCode::PageUp => self.move_selection(-1),
+                KeyCode::Down | KeyCode::PageDown => self.move_selection(1),
+                _ => {
+                    if let Some(action) = self.keymap.action(code) {
+                        self.perform(action).await;
                     }
                 }
             }
+        } else {
+            // Popup open, only allow quit, unless the popup is taking text
+            if key.code == KeyCode::Char('q') && !self.popup_state.takes_text() {
+                self.should_quit = true;
+            }
         }
     }
 
     fn draw(&self, f: &mut Frame) {
         let size = f.area();
+        let show_footer = footer_visible(self.config.show_footer, size.height);
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),
                 Constraint::Min(0),
-                Constraint::Length(1),
+                Constraint::Length(if show_footer { 1 } else { 0 }),
             ])
             .split(size);
 
-        let title = Paragraph::new("🐀 Ratifact - Build Artifact Purge Tool")
+        let active = self.tasks.active();
+        let title_text = if active > 0 {
+            let frame = SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()];
+            format!("🐀 Ratifact - Build Artifact Purge Tool  {} working ({})", frame, active)
+        } else {
+            "🐀 Ratifact - Build Artifact Purge Tool".to_string()
+        };
+        let title = Paragraph::new(title_text)
             .style(
                 Style::default()
                     .fg(Color::Cyan)
@@ -354,32 +799,52 @@ impl App {
 
         self.popup_state.draw(f, size);
 
-        let footer = Paragraph::new("Tab: Focus | s: Scan | d: Delete | x: Exclude | r: Rebuild | e: Settings | l: Logs | Shift+D: Clear All | q: Quit")
-            .style(Style::default().fg(Color::Black).bg(Color::LightGreen));
-        f.render_widget(footer, chunks[2]);
+        if show_footer {
+            let k = |action| self.keymap.label(action);
+            let footer = Paragraph::new(format!(
+                "Tab: Focus | {}: Scan | {}: Delete | {}/{}: Exclude (path/type) | {}: Rebuild | {}: Protect | {}: Note | {}: Copy Path | {}: Compare | {}: Usage | {}: Orphans | {}: Watch | {}: Auto-Remove ({}) | {}: Settings | {}: Logs | {}: Reconnect DB | {}: Clear All | {}: Hide Footer | {}: Help | {}: Quit",
+                k(Action::Scan), k(Action::Delete), k(Action::Exclude), k(Action::ExcludeType), k(Action::Rebuild),
+                k(Action::Protect), k(Action::Note), k(Action::CopyPath), k(Action::Compare), k(Action::Usage),
+                k(Action::Orphans), k(Action::ToggleWatcher), k(Action::ToggleRemoval),
+                if self.automatic_removal { "on" } else { "off" },
+                k(Action::Settings), k(Action::Logs), k(Action::Reconnect), k(Action::ClearAll),
+                k(Action::ToggleFooter), k(Action::Help), k(Action::Quit)
+            ))
+                .style(Style::default().fg(Color::Black).bg(Color::LightGreen));
+            f.render_widget(footer, chunks[2]);
+        }
+
+        if self.no_color {
+            strip_colors(f);
+        }
     }
 
     fn draw_overview_all_panels(&self, f: &mut Frame, area: Rect) {
-        // Grid layout: 2 rows, 3 columns for 5 panels
+        if is_compact_layout(f.area()) {
+            self.draw_compact_panel(f, area);
+            return;
+        }
+
+        // Grid layout: 2 rows, 3 columns for 5 panels, sized by the saved panel layout
+        let layout = self.config.panel_layout.clamped();
         let rows = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(8), Constraint::Min(8)])
+            .constraints([
+                Constraint::Percentage(layout.top_row_percent),
+                Constraint::Percentage(100 - layout.top_row_percent),
+            ])
             .split(area);
 
         let _top_row = Layout::default()
             .direction(Direction::Horizontal)
             .margin(1)
-            .constraints([
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-                Constraint::Ratio(1, 3),
-            ])
+            .constraints(layout.widths[..3].iter().map(|w| Constraint::Fill(*w)))
             .split(rows[0]);
 
         let bottom_row = Layout::default()
             .direction(Direction::Horizontal)
             .margin(1)
-            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .constraints(layout.widths[3..].iter().map(|w| Constraint::Fill(*w)))
             .split(rows[1]);
 
         // Top row: Artifacts, History, Charts
@@ -392,55 +857,150 @@ impl App {
         self.draw_overview_summary(f, bottom_row[1], self.focused_panel == 4);
     }
 
+    fn draw_compact_panel(&self, f: &mut Frame, area: Rect) {
+        // Small terminals get one full-size panel at a time; Tab still cycles through them
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(area);
+        let tabs = PANEL_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| if i == self.focused_panel { format!("[{}]", name) } else { name.to_string() })
+            .collect::<Vec<_>>()
+            .join(" ");
+        f.render_widget(Paragraph::new(tabs).style(Style::default().fg(Color::Yellow)), rows[0]);
+
+        match self.focused_panel {
+            0 => self.draw_artifacts_mini(f, rows[1], true),
+            1 => self.draw_history_mini(f, rows[1], true),
+            2 => self.draw_charts_mini(f, rows[1], true),
+            3 => self.draw_settings_mini(f, rows[1], true),
+            _ => self.draw_overview_summary(f, rows[1], true),
+        }
+    }
+
     fn draw_artifacts_mini(&self, f: &mut Frame, area: Rect, focused: bool) {
         let border_style = if focused {
             Style::default().fg(Color::Yellow)
         } else {
             Style::default()
         };
-        let (start, take_count) = (0, self.artifacts.len());
         let scan_path = self.config.scan_paths.first().map(|s| s.as_str()).unwrap_or("");
-        let items: Vec<ListItem> = self
-            .artifacts
-            .iter()
-            .skip(start)
-            .take(take_count)
+        let roots: Vec<String> = self.config.scan_paths.iter().map(|p| normalize_path(p)).collect();
+        // Only worth a tag when artifacts can come from more than one root
+        let tagged = roots.len() > 1;
+        let palette = Palette::new(self.config.color_blind_palette);
+        // Borders and padding take 4 columns; the icon (2 wide) and its space another 3, the root tag 2 more
+        let path_width = area.width.saturating_sub(if tagged { 9 } else { 7 }) as usize;
+        let visible = self.visible_artifacts();
+        let excluded = self.shown_excluded();
+        let first_excluded = visible.len();
+        let empty_message = (visible.is_empty() && excluded.is_empty()).then(|| {
+            artifacts_empty_message(self.scanning, self.scanned, self.artifacts.len(), self.artifact_filter, &self.keymap)
+        });
+        let mut items: Vec<ListItem> = visible
+            .into_iter()
             .enumerate()
             .map(|(i, a)| {
-                // Strip scan path prefix
-                let relative_path = if let Some(stripped) = a.strip_prefix(&format!("{}/", scan_path)) {
-                    stripped
+                // Strip the prefix of the scan path the artifact came from
+                let origin = self.origins.get(a.as_str());
+                let relative_path = origin
+                    .and_then(|root| a.strip_prefix(&format!("{}/", root)))
+                    .or_else(|| a.strip_prefix(&format!("{}/", scan_path)))
+                    .filter(|_| !self.config.absolute_paths)
+                    .unwrap_or(a);
+                let tag = origin
+                    .and_then(|root| roots.iter().position(|r| r == root))
+                    .filter(|_| tagged)
+                    .map(|index| Span::styled("▌ ", Style::default().fg(ROOT_COLORS[index % ROOT_COLORS.len()])))
+                    .unwrap_or_else(|| Span::raw(if tagged { "  " } else { "" }));
+                let icon = if self.is_protected(a) {
+                    "🔒"
+                } else if is_docker_artifact(a) {
+                    "🐳"
+                } else if self.orphaned.contains(a) {
+                    "👻"
+                } else if self.virtualenvs.contains(a) {
+                    "🐍"
                 } else {
-                    a
+                    "📁"
                 };
-                let color = if a.contains("target") {
-                    Color::Green
+                let color = if is_docker_artifact(a) {
+                    palette.docker
+                } else if self.virtualenvs.contains(a.as_str()) {
+                    palette.virtualenv
+                } else if a.contains("target") {
+                    palette.target
                 } else if a.contains("node_modules") {
-                    Color::Blue
+                    palette.node_modules
                 } else if a.contains("__pycache__") {
-                    Color::Yellow
+                    palette.pycache
                 } else if a.contains("build") {
-                    Color::Red
+                    palette.build
                 } else {
-                    Color::White
+                    palette.other
                 };
-                let style = if focused && i + start == self.selected {
+                let oversized = self.artifact_size(a).is_some_and(|size| self.is_oversized(size));
+                let color = if oversized { palette.alert } else { color };
+                let alert = if oversized { "⚠️ " } else { "" };
+                let note = if self.notes.contains_key(a.as_str()) { " 📝" } else { "" };
+                let mark = if self.marked.contains(a.as_str()) { "✓ " } else { "" };
+                let relative_path = truncate_middle(
+                    relative_path,
+                    path_width.saturating_sub(if oversized { 3 } else { 0 } + if note.is_empty() { 0 } else { 3 } + if mark.is_empty() { 0 } else { 2 }),
+                );
+                let style = if focused && i == self.selected {
                     Style::default().bg(Color::Blue).fg(Color::Black)
                 } else {
                     Style::default().fg(color)
                 };
-                ListItem::new(Span::styled(format!("📁 {}", relative_path), style))
+                ListItem::new(Line::from(vec![
+                    tag,
+                    Span::styled(format!("{}{}{} {}{}", mark, alert, icon, relative_path, note), style),
+                ]))
             })
             .collect();
+        items.extend(excluded.iter().enumerate().map(|(i, a)| {
+            let relative_path = self
+                .config
+                .scan_paths
+                .iter()
+                .find_map(|root| a.strip_prefix(&format!("{}/", normalize_path(root))))
+                .filter(|_| !self.config.absolute_paths)
+                .unwrap_or(a);
+            let style = if focused && first_excluded + i == self.selected {
+                Style::default().bg(Color::DarkGray).fg(Color::Black)
+            } else {
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM)
+            };
+            let text = format!("🚫 {}", truncate_middle(relative_path, path_width));
+            ListItem::new(Line::from(vec![Span::raw(if tagged { "  " } else { "" }), Span::styled(text, style)]))
+        }));
+        if let Some(message) = empty_message {
+            items.push(ListItem::new(Span::styled(message, Style::default().fg(Color::DarkGray))));
+        }
         let mut state = ListState::default();
         state.select(Some(self.selected));
-        let list = List::new(items).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(border_style)
-                .title("📦 Artifacts")
-                .padding(Padding::new(1,1,1,0)),
-        );
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title(self.artifacts_title())
+            .padding(Padding::new(1,1,1,0));
+        if tagged {
+            // Legend: one colored tag per scan path
+            let legend: Vec<Span> = self
+                .config
+                .scan_paths
+                .iter()
+                .enumerate()
+                .map(|(index, path)| {
+                    Span::styled(format!(" ▌{} ", path), Style::default().fg(ROOT_COLORS[index % ROOT_COLORS.len()]))
+                })
+                .collect();
+            block = block.title_bottom(Line::from(legend));
+        }
+        let list = List::new(items).block(block);
         f.render_stateful_widget(list, area, &mut state);
     }
 
@@ -450,7 +1010,36 @@ impl App {
         } else {
             Style::default()
         };
-        let history_text = self.build_history.join("\n");
+        let width = area.width.saturating_sub(4) as usize;
+        if self.build_history.is_empty() {
+            let hint = if self.scanning {
+                "Builds are recorded as the scan finds artifacts...".to_string()
+            } else {
+                format!("No builds recorded yet - press {} to scan, {} to reload", self.keymap.label(Action::Scan), self.keymap.label(Action::History))
+            };
+            let para = Paragraph::new(Span::styled(hint, Style::default().fg(Color::DarkGray))).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title("📜 History")
+                    .padding(Padding::new(1,1,1,0)),
+            );
+            f.render_widget(para, area);
+            return;
+        }
+        let history_text = self
+            .build_history
+            .iter()
+            .map(|line| match line.rsplitn(3, " - ").collect::<Vec<_>>()[..] {
+                // Shorten the project path and keep the language and time readable
+                [time, language, project] => {
+                    let rest = format!(" - {} - {}", language, time);
+                    format!("{}{}", truncate_middle(project, width.saturating_sub(rest.chars().count())), rest)
+                }
+                _ => line.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
         let para = Paragraph::new(history_text).block(
             Block::default()
                 .borders(Borders::ALL)
@@ -462,36 +1051,58 @@ impl App {
     }
 
     fn draw_charts_mini(&self, f: &mut Frame, area: Rect, focused: bool) {
+        if self.chart_by_age {
+            self.draw_age_histogram(f, area, focused);
+            return;
+        }
         let border_style = if focused {
             Style::default().fg(Color::Yellow)
         } else {
             Style::default()
         };
-        let items: Vec<ListItem> = if self.chart_data.is_empty() {
-            vec![ListItem::new("No data")]
+        let chart_data = self.visible_chart_data();
+        let items: Vec<ListItem> = if chart_data.is_empty() {
+            let message = if self.chart_data.is_empty() {
+                format!("No sizes yet - press {} to scan, then the largest artifacts are charted here", self.keymap.label(Action::Scan))
+            } else if let Some(days) = self.chart_range
+                && !self.chart_data.iter().any(|(path, _)| self.chart_trends.contains_key(path))
+            {
+                format!("No sizes recorded in the last {}d ({}: next range)", days, self.keymap.label(Action::ChartRange))
+            } else {
+                "Nothing above the size threshold (-: lower)".to_string()
+            };
+            vec![ListItem::new(Span::styled(message, Style::default().fg(Color::DarkGray)))]
         } else {
-            let max_size = self.chart_data.iter().map(|(_, s)| *s).max().unwrap_or(1);
-            let colors = [Color::Red, Color::Green, Color::Blue, Color::Yellow, Color::Magenta, Color::Cyan, Color::White];
+            let max_size = chart_data.iter().map(|(_, s)| *s).max().unwrap_or(1);
+            let palette = Palette::new(self.config.color_blind_palette);
             let scan_path = self.config.scan_paths.first().map(|s| s.as_str()).unwrap_or("");
             // Calculate available width for bars: area.width - borders(2) - padding(2) - name(15) - spaces(2) - size(10)
             let available_width = area.width.saturating_sub(31).max(10) as u64;
-            self.chart_data.iter().enumerate().map(|(i, (name, size))| {
-                let bar_len = if max_size > 0 { (size * available_width / max_size) as usize } else { 0 };
+            chart_data.iter().enumerate().map(|(i, (name, size))| {
+                let bar_len = (size * available_width).checked_div(max_size).unwrap_or(0) as usize;
                 let bar = "█".repeat(bar_len);
                 let size_mb = size / 1_000_000;
-                let color = colors[i % colors.len()];
+                let oversized = self.is_oversized(*size);
+                let color = if oversized { palette.alert } else { palette.chart[i % palette.chart.len()] };
+                let alert = if oversized { " ⚠️" } else { "" };
+                let trend = self
+                    .chart_range
+                    .and_then(|_| self.chart_trends.get(name))
+                    .map(|growth| format!(" {}", format_growth(*growth)))
+                    .unwrap_or_default();
                 let style = if focused && i == self.chart_selected {
                     Style::default().bg(Color::Blue).fg(Color::White).add_modifier(Modifier::BOLD)
                 } else {
                     Style::default().fg(color)
                 };
-                let relative_name = if let Some(stripped) = name.strip_prefix(&format!("{}/", scan_path)) {
-                    stripped
+                let short_name = if self.config.absolute_paths {
+                    // The start of an absolute path is the same for every bar, so keep its tail
+                    truncate_middle(name, 15)
                 } else {
-                    name
+                    let relative_name = name.strip_prefix(&format!("{}/", scan_path)).unwrap_or(name);
+                    if relative_name.len() > 15 { format!("{}...", &relative_name[..12]) } else { relative_name.to_string() }
                 };
-                let short_name = if relative_name.len() > 15 { format!("{}...", &relative_name[..12]) } else { relative_name.to_string() };
-                ListItem::new(Span::styled(format!("{:<15} {} {}MB\n", short_name, bar, size_mb), style))
+                ListItem::new(Span::styled(format!("{:<15} {} {}MB{}{}\n", short_name, bar, size_mb, trend, alert), style))
             }).collect()
         };
         let mut state = ListState::default();
@@ -500,12 +1111,52 @@ impl App {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(border_style)
-                .title("📊 Charts")
+                .title(self.charts_title())
                 .padding(Padding::new(1,1,1,0)),
         );
         f.render_stateful_widget(list, area, &mut state);
     }
 
+    fn draw_age_histogram(&self, f: &mut Frame, area: Rect, focused: bool) {
+        let border_style = if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title(format!("📊 Artifact Age ({}: sizes)", self.keymap.label(Action::ChartAge)))
+            .padding(Padding::new(1,1,1,0));
+        if self.age_buckets.iter().all(|b| b.artifact_count == 0) {
+            let hint = Span::styled(format!("No artifacts recorded yet - press {} to scan", self.keymap.label(Action::Scan)), Style::default().fg(Color::DarkGray));
+            f.render_widget(Paragraph::new(hint).block(block), area);
+            return;
+        }
+        // Fresh builds green, stale ones red: the right-hand bars are what retention can reclaim
+        let bars: Vec<Bar> = self
+            .age_buckets
+            .iter()
+            .zip(Palette::new(self.config.color_blind_palette).age)
+            .map(|(bucket, color)| {
+                Bar::default()
+                    .value(bucket.total_size)
+                    .text_value(format_bytes(bucket.total_size))
+                    .label(Line::from(format!("{} ({})", bucket.label, bucket.artifact_count)))
+                    .style(Style::default().fg(color))
+            })
+            .collect();
+        let gap = 2;
+        let inner_width = area.width.saturating_sub(4);
+        let bar_width = (inner_width.saturating_sub(gap * 3) / 4).max(1);
+        let chart = BarChart::default()
+            .block(block)
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(gap);
+        f.render_widget(chart, area);
+    }
+
     fn draw_settings_mini(&self, f: &mut Frame, area: Rect, focused: bool) {
         let border_style = if focused {
             Style::default().fg(Color::Yellow)
@@ -515,13 +1166,16 @@ impl App {
         let masked_db = Self::mask_db_url(&self.config.database_url);
         let removal_status = if self.automatic_removal { "Enabled" } else { "Disabled" };
         let excluded_count = self.config.excluded_paths.len();
+        let docker_status = if self.config.clean_docker { "Enabled" } else { "Disabled" };
         let text = format!(
-            "DB: {}\nPaths: {}\nRetention Days: {}\nAutomatic Removal: {}\nExcluded Paths: {}",
+            "DB: {}\nPaths: {}\nRetention Days: {}\nAutomatic Removal: {}\nExcluded Paths: {}\nProtected Paths: {}\nDocker Cleanup: {}",
             masked_db,
             self.config.scan_paths.join(","),
             self.config.retention_days,
             removal_status,
-            excluded_count
+            excluded_count,
+            self.config.protected_paths.len(),
+            docker_status
         );
         let para = Paragraph::new(text).block(
             Block::default()
@@ -541,11 +1195,63 @@ impl App {
         } else {
             Style::default()
         };
-        let summary = format!(
-            "🏗️ Total Builds: {}\n📦 Artifacts: {}\n🔍 Scans: Active\n⚡ Watcher: Running",
+        let db_status = if self.db_healthy.load(Ordering::SeqCst) {
+            "🟢 Connected".to_string()
+        } else {
+            format!("🔴 Unreachable ({} to reconnect)", self.keymap.label(Action::Reconnect))
+        };
+        let watcher_status = match self.watcher.health() {
+            WatcherHealth::Failed(e) => format!("🔴 Failed to start: {}", e),
+            _ if !self.config.watcher_enabled => "⚪ Off".to_string(),
+            WatcherHealth::LimitReached(n) => format!("🔴 Watch limit reached ({} paths)", n),
+            WatcherHealth::Watching(n) => format!("🟢 Watching {} paths", n),
+        };
+        let scan_status = if self.scanning { "Scanning..." } else { "Idle" };
+        let mut summary = format!(
+            "🏗️ Total Builds: {}\n📦 Artifacts: {}\n🗄️ Database: {}\n🔍 Scans: {}\n⚡ Watcher: {}",
             self.total_builds,
-            self.artifacts.len()
+            self.artifacts.len(),
+            db_status,
+            scan_status,
+            watcher_status
         );
+        let changed = self.changed_artifact_count();
+        if changed > 0 {
+            let noun = if changed == 1 { "artifact" } else { "artifacts" };
+            summary.push_str(&format!("\n⚠️ {} {} changed — rescan recommended", changed, noun));
+        }
+        // The recorded runs include this session's scans; the in-memory one covers a failed insert
+        let durations = match self.last_scan_duration {
+            Some(last) if self.scan_durations.is_empty() => vec![last],
+            _ => self.scan_durations.clone(),
+        };
+        if let Some(trend) = scan_duration_summary(&durations) {
+            summary.push_str(&format!("\n⏱️ Last scan: {}", trend));
+        }
+        for (path, used, budget) in self.budget_usages() {
+            summary.push_str(&format!(
+                "\n📏 Budget {}: {} {} / {}",
+                path, budget_gauge(used, budget, BUDGET_GAUGE_WIDTH), format_bytes(used), format_bytes(budget)
+            ));
+            if used > budget {
+                summary.push_str(&format!(" — over by {} ({} to mark what to delete)", format_bytes(used - budget), self.keymap.label(Action::BudgetPlan)));
+            }
+        }
+        if let Some((path, free)) = &self.free_space {
+            summary.push_str(&format!("\n💽 Free space: {} on {}", format_bytes(*free), path));
+            if self.config.cleanup_when_free_below_bytes.is_some_and(|threshold| *free < threshold) {
+                summary.push_str(" (below cleanup threshold)");
+            }
+        }
+        if self.read_only {
+            summary.push_str("\n🔒 Read-only mode");
+        }
+        if let Some(remaining) = self.cleanup_snooze_remaining() {
+            summary.push_str(&format!("\n💤 Auto-cleanup snoozed: {} left", format_duration_short(remaining)));
+        }
+        if let Some(note) = self.selected_artifact().and_then(|path| self.notes.get(&path)) {
+            summary.push_str(&format!("\n📝 Note: {}", note));
+        }
         let para = Paragraph::new(summary).block(
             Block::default()
                 .borders(Borders::ALL)
@@ -558,181 +1264,862 @@ impl App {
 
 
 
-    async fn trigger_scan(&mut self) {
-        self.scanning = true;
-        self.popup_state = PopupState::Scanning { logs: Arc::clone(&self.logs) };
-        let scan_paths = if self.config.scan_paths.is_empty() {
-            vec![".".to_string()]
-        } else {
-            self.config.scan_paths.clone()
+    fn write_scan_report(&mut self, sizes: &HashMap<String, u64>, duration: Duration) {
+        let Some(dir) = self.config.scan_report_dir.clone().filter(|dir| !dir.trim().is_empty()) else {
+            return;
         };
-        let excluded_paths = self.config.excluded_paths.clone();
-        let logs_clone = Arc::clone(&self.logs);
-        let artifacts_clone = Arc::new(Mutex::new(vec![]));
-        let _artifacts_clone2 = Arc::clone(&artifacts_clone);
-        let logger_clone = self.logger.clone();
-        let mut watcher_clone = self.watcher.clone();
-        let _config_clone = self.config.clone();
-        let tx_clone = self.scan_result_tx.clone();
-        tokio::spawn(async move {
-            {
-                let mut logs = logs_clone.lock().unwrap();
-                logs.push("Starting scan...".to_string());
-            }
-            let common_dirs = [
-                // Rust
-                "target",
-                // C/C++
-                "build",
-                ".build",
-                "cmake-build-debug",
-                "cmake-build-release",
-                "Debug",
-                "Release",
-                // JavaScript/TypeScript
-                "node_modules",
-                "dist",
-                ".next",
-                ".parcel-cache",
-                ".cache",
-                // Python
-                "__pycache__",
-                ".eggs",
-                "eggs",
-                // Java/Gradle
-                ".gradle",
-                // PHP/Composer
-                "vendor",
-                // Ruby
-                ".bundle",
-                // General build outputs
-                "out",
-                ".output",
-                ".nyc_output",
-            ];
-            let mut total_count = 0;
-            for scan_path in scan_paths {
-                {
-                    let mut logs = logs_clone.lock().unwrap();
-                    logs.push(format!("Scanning path: {}", scan_path));
-                }
-                let mut count = 0;
-                for entry in WalkDir::new(&scan_path)
-                    .max_depth(3)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                {
-                    if entry.file_type().is_dir() {
-                        let name = entry.file_name().to_string_lossy();
-                        let path_str = entry.path().display().to_string();
-
-                        // Check if path is in excluded list
-                        let is_excluded = excluded_paths.iter().any(|ex| path_str.contains(ex));
-
-                        if common_dirs.contains(&name.as_ref()) && !is_excluded {
-                            let project_path = entry.path().parent().unwrap_or(Path::new(".")).display().to_string();
-                            let language = detect_language_for_path(&project_path);
-                            let size = calculate_dir_size(&path_str);
-                            {
-                                let mut artifacts = artifacts_clone.lock().unwrap();
-                                artifacts.push(path_str.clone());
-                            }
-                            count += 1;
-                            // Log to DB
-                            let _ = logger_clone
-                                .log_build(&project_path, &language, &path_str, size)
-                                .await;
-                            // Start watching
-                            let _ = watcher_clone.watch(&path_str);
-                        }
-                    }
-                }
-                total_count += count;
-                {
-                    let mut logs = logs_clone.lock().unwrap();
-                    logs.push(format!("Scan complete for {}. Found {} artifacts.", scan_path, count));
-                }
-            }
-            let artifacts = artifacts_clone.lock().unwrap().clone();
-            let _ = tx_clone.send(artifacts).await;
-            {
-                let mut logs = logs_clone.lock().unwrap();
-                logs.push(format!("Total scan complete. Found {} artifacts.", total_count));
-            }
-        });
+        let message = save_scan_report(&dir, &self.artifacts, sizes, duration);
+        self.logs.lock().unwrap().push(message);
+    }
+
+    /// Configured budgets with what the listed artifacts under each path take up.
+    fn budget_usages(&self) -> Vec<(String, u64, u64)> {
+        self.config
+            .size_budgets
+            .iter()
+            .map(|(path, budget)| (path.clone(), budget_usage(path, &self.latest_sizes), *budget))
+            .collect()
     }
 
-    async fn delete_selected(&mut self) {
-        if self.artifacts.is_empty() {
+    async fn mark_budget_plan(&mut self) {
+        let over: Vec<(String, u64, u64)> = self.budget_usages().into_iter().filter(|(_, used, budget)| used > budget).collect();
+        if over.is_empty() {
+            let message = if self.config.size_budgets.is_empty() {
+                "No size budgets set. Add a [size_budgets] table to config.toml.".to_string()
+            } else {
+                "Every scan path is within its size budget.".to_string()
+            };
+            self.popup_state = PopupState::Info { message };
             return;
         }
-        let path = self.artifacts[self.selected].clone();
-        // Try sudo -n first (no password required)
-        if self.delete_with_sudo(&path, None) {
-            self.artifacts.remove(self.selected);
-            if self.selected >= self.artifacts.len() && self.selected > 0 {
-                self.selected -= 1;
+        let candidates = match crate::db::schema::fetch_latest_sizes(&self.logger.pool).await {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                self.popup_state = PopupState::Error { message: format!("Could not list artifacts: {}", e) };
+                return;
             }
-            // Update DB
-            let _ = sqlx::query("DELETE FROM builds WHERE artifact_path = $1").bind(&path).execute(&self.logger.pool).await;
-            self.popup_state = PopupState::Info { message: "Artifact deleted.".to_string() };
-        } else {
-            // Prompt for password
-            self.pending_action = Some("delete".to_string());
-            self.popup_state = PopupState::new_input("Enter sudo password".to_string(), "".to_string());
+        };
+        let listed: HashSet<&str> = self.artifacts.iter().map(String::as_str).collect();
+        let mut lines = vec![];
+        let mut planned_all: Vec<String> = vec![];
+        for (path, used, budget) in over {
+            let root = normalize_path(&path);
+            // Least recently modified first, as with free-space cleanup; protected ones are never suggested
+            let on_root: Vec<(String, u64)> = candidates
+                .iter()
+                .filter(|(artifact, _)| {
+                    listed.contains(artifact.as_str())
+                        && Path::new(artifact).starts_with(&root)
+                        && !self.is_protected(artifact)
+                        && !planned_all.contains(artifact)
+                })
+                .cloned()
+                .collect();
+            let on_root = tokio::task::spawn_blocking(move || least_recently_modified(&on_root, ACTIVE_WITHIN_DAYS))
+                .await
+                .unwrap_or_default();
+            let planned = free_space_plan(&on_root, 0, used - budget);
+            let freed: u64 = on_root.iter().filter(|(artifact, _)| planned.contains(artifact)).map(|(_, size)| size).sum();
+            let outcome = if freed >= used - budget { "" } else { " (not enough unprotected, idle artifacts to get under)" };
+            lines.push(format!(
+                "{}: over by {}, marked {} artifacts ({}){}",
+                path, format_bytes(used - budget), planned.len(), format_bytes(freed), outcome
+            ));
+            planned_all.extend(planned);
         }
+        self.marked.extend(planned_all);
+        self.focused_panel = 0;
+        self.popup_state = PopupState::Info {
+            message: format!(
+                "📏 Size budgets\n\n{}\n\nReview the marked artifacts, then press {} to delete them.",
+                lines.join("\n"),
+                self.keymap.label(Action::Delete)
+            ),
+        };
     }
 
-    async fn load_artifacts(&mut self) {
-        // Query DB for recent artifact paths
-        match sqlx::query("SELECT artifact_path FROM builds GROUP BY artifact_path ORDER BY MAX(build_time) DESC LIMIT 50")
-            .fetch_all(&self.logger.pool)
-            .await
-        {
-            Ok(rows) => {
-                for row in rows {
-                    let path: String = row.get(0);
-                    self.artifacts.push(path.clone());
-                }
-            }
-            Err(_) => {
-                // Ignore errors, start empty
-            }
+    fn changed_artifact_count(&self) -> usize {
+        // Our own deletions also fire events, but those artifacts are already off the list
+        let listed: HashSet<&str> = self.artifacts.iter().map(String::as_str).collect();
+        self.watcher.changed_paths().iter().filter(|path| listed.contains(path_to_string(path).as_str())).count()
+    }
+
+    fn refresh_free_space(&mut self) {
+        // The fullest disk among the scan paths is the one that matters
+        self.free_space = self
+            .config
+            .scan_paths
+            .iter()
+            .filter_map(|path| free_space_bytes(path).map(|free| (path.clone(), free)))
+            .min_by_key(|(_, free)| *free);
+    }
+
+    fn spawn_tracked<F>(&mut self, label: impl Into<String>, kind: TaskKind, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        // Tracked so the title bar can show a spinner and quitting can wind the work down
+        self.tasks.spawn(label, kind, task);
+    }
+
+    /// Background tasks quitting will wait for.
+    pub fn pending_critical_tasks(&self) -> Vec<String> {
+        self.tasks.pending(TaskKind::Critical)
+    }
+
+    /// Saves the focused panel, stops scans and refreshes, and waits up to SHUTDOWN_GRACE for
+    /// deletions to finish.
+    pub async fn shutdown(&mut self) -> Vec<String> {
+        // Remembered once on the way out rather than written on every Tab
+        if self.config.last_focused_panel != self.focused_panel {
+            self.config.last_focused_panel = self.focused_panel;
+            self.save_config();
         }
+        self.tasks.shutdown(SHUTDOWN_GRACE).await
     }
 
-    async fn load_history(&mut self) {
-        // Query DB for build history
-        match sqlx::query("SELECT project_path, language, build_time FROM builds ORDER BY build_time DESC LIMIT 10")
-            .fetch_all(&self.logger.pool)
-            .await
-        {
-            Ok(rows) => {
-                self.build_history = rows
-                    .into_iter()
-                    .map(|row| {
-                        let project: String = row.get(0);
-                        let language: String = row.get(1);
-                        let time: DateTime<Utc> = row.get(2);
-                        format!("{} - {} - {}", project, language, time.format("%Y-%m-%d %H:%M"))
-                    })
-                    .collect();
-            }
-            Err(_) => {
-                self.build_history = vec!["Failed to load history".to_string()];
-            }
+    async fn trigger_scan(&mut self) {
+        self.scanning = true;
+        self.watcher.reset_changes();
+        self.scan_generation += 1;
+        let generation = self.scan_generation;
+        self.popup_state = PopupState::new_scanning(Arc::clone(&self.logs));
+        if !self.use_language_cache {
+            self.language_cache.lock().unwrap().clear();
         }
-        match sqlx::query("SELECT COUNT(*) FROM builds")
-            .fetch_one(&self.logger.pool)
-            .await
-        {
-            Ok(row) => {
-                self.total_builds = row.get::<i64, _>(0) as usize;
+        let job = ScanJob::new(&self.config, self.logger.clone(), Some(self.watcher.clone()), Arc::clone(&self.language_cache));
+        let logs_clone = Arc::clone(&self.logs);
+        let tx_clone = self.scan_result_tx.clone();
+        self.spawn_tracked("scan", TaskKind::Cancellable, async move {
+            let mut report = job.run(logs_clone).await;
+            report.generation = generation;
+            // The receiver only goes away when the app is shutting down
+            if let Err(e) = tx_clone.send(report).await {
+                log_to_file(&format!("Scan #{} finished but its result could not be delivered: {}", generation, e));
+            }
+        });
+    }
+
+    fn visible_artifacts(&self) -> Vec<&String> {
+        // `selected` indexes into this list, not into `artifacts`
+        self.artifacts
+            .iter()
+            .filter(|a| match self.artifact_filter {
+                ArtifactFilter::All => true,
+                ArtifactFilter::Orphaned => self.orphaned.contains(*a),
+                ArtifactFilter::Expired => self.expired.contains(*a),
+                ArtifactFilter::Safe => self.safe.contains(*a),
+                ArtifactFilter::Active => self.active.contains(*a),
+            })
+            .collect()
+    }
+
+    fn selected_artifact(&self) -> Option<String> {
+        self.visible_artifacts().get(self.selected).map(|a| a.to_string())
+    }
+
+    fn shown_excluded(&self) -> &[String] {
+        if self.show_excluded { &self.excluded_hits } else { &[] }
+    }
+
+    /// Artifact rows plus the excluded ones listed after them.
+    fn artifact_row_count(&self) -> usize {
+        self.visible_artifacts().len() + self.shown_excluded().len()
+    }
+
+    /// The excluded match under the cursor; these rows sit after the regular ones so no
+    /// delete or Clear All can reach them.
+    fn selected_excluded(&self) -> Option<String> {
+        let index = self.selected.checked_sub(self.visible_artifacts().len())?;
+        self.shown_excluded().get(index).cloned()
+    }
+
+    fn toggle_show_excluded(&mut self) {
+        self.show_excluded = !self.show_excluded;
+        self.selected = self.selected.min(self.artifact_row_count().saturating_sub(1));
+        self.focused_panel = 0;
+    }
+
+    async fn unexclude_selected(&mut self) {
+        let Some(path) = self.selected_excluded() else {
+            return;
+        };
+        // Drop every entry hiding it: its own path, a parent, or a type exclusion
+        let entries: Vec<String> = self
+            .config
+            .excluded_paths
+            .iter()
+            // Scans compare against normalized entries, so older non-canonical ones still match here
+            .filter(|entry| is_path_excluded(&path, &[normalize_path(entry)]))
+            .cloned()
+            .collect();
+        if entries.is_empty() {
+            self.popup_state = PopupState::Info {
+                message: format!("{} is excluded by a .ratifactignore in its project.\nEdit that file to list it again.", path),
+            };
+            return;
+        }
+        self.record_undo(format!("un-exclude {}", path));
+        self.config.excluded_paths.retain(|entry| !entries.contains(entry));
+        self.save_config();
+        self.popup_state = PopupState::Info {
+            message: format!("Removed from the exclusion list:\n{}\n\nRescanning...", entries.join("\n")),
+        };
+        if !self.scanning {
+            self.trigger_scan().await;
+        }
+    }
+
+    fn remove_from_list(&mut self, path: &str) {
+        self.artifacts.retain(|a| a != path);
+        self.orphaned.remove(path);
+        self.marked.remove(path);
+        let visible = self.visible_artifacts().len();
+        if self.selected >= visible {
+            self.selected = visible.saturating_sub(1);
+        }
+    }
+
+    fn marked_visible(&self) -> Vec<String> {
+        self.visible_artifacts().into_iter().filter(|a| self.marked.contains(*a)).cloned().collect()
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+    }
+
+    fn mark_all_visible(&mut self) {
+        let visible: Vec<String> = self.visible_artifacts().into_iter().cloned().collect();
+        self.marked.extend(visible);
+    }
+
+    fn invert_marks(&mut self) {
+        let visible: Vec<String> = self.visible_artifacts().into_iter().cloned().collect();
+        self.marked = invert_marks(&self.marked, &visible);
+    }
+
+    fn clear_filter_or_marks(&mut self) {
+        // First Esc drops the filter, the next one the marks
+        if self.artifact_filter != ArtifactFilter::All {
+            self.artifact_filter = ArtifactFilter::All;
+            self.selected = 0;
+        } else {
+            self.marked.clear();
+        }
+    }
+
+    fn toggle_orphan_filter(&mut self) {
+        self.artifact_filter = match self.artifact_filter {
+            ArtifactFilter::Orphaned => ArtifactFilter::All,
+            _ => ArtifactFilter::Orphaned,
+        };
+        self.selected = 0;
+        self.focused_panel = 0;
+    }
+
+    async fn toggle_expired_filter(&mut self) {
+        self.artifact_filter = match self.artifact_filter {
+            ArtifactFilter::Expired => ArtifactFilter::All,
+            _ => {
+                // Asked fresh each time so a changed retention setting shows up right away
+                self.load_cleanup_targets().await;
+                ArtifactFilter::Expired
+            }
+        };
+        self.selected = 0;
+        self.focused_panel = 0;
+    }
+
+    async fn cycle_category_filter(&mut self) {
+        self.artifact_filter = match self.artifact_filter {
+            ArtifactFilter::Safe => ArtifactFilter::Active,
+            ArtifactFilter::Active => ArtifactFilter::All,
+            _ => ArtifactFilter::Safe,
+        };
+        if self.artifact_filter != ArtifactFilter::All {
+            self.load_categories().await;
+        }
+        self.selected = 0;
+        self.focused_panel = 0;
+    }
+
+    async fn load_categories(&mut self) {
+        // Walks each artifact for its newest mtime (atime is reset by the scan itself), so only
+        // done when a category is picked or a scan lands
+        let changed: HashSet<String> = self.watcher.changed_paths().iter().map(|path| path_to_string(path)).collect();
+        // Docker entries aren't directories, so they are never stale or recently used by this measure
+        let paths: Vec<String> = self.artifacts.iter().filter(|path| !is_docker_artifact(path)).cloned().collect();
+        let modified: HashMap<String, SystemTime> = tokio::task::spawn_blocking(move || {
+            paths.into_iter().filter_map(|path| content_modified(&path).map(|modified| (path, modified))).collect()
+        })
+        .await
+        .unwrap_or_default();
+        self.safe.clear();
+        self.active.clear();
+        for path in &self.artifacts {
+            let modified = modified.get(path).copied();
+            let active = changed.contains(path) || modified_within_days(modified, ACTIVE_WITHIN_DAYS);
+            let stale = !is_docker_artifact(path) && !modified_within_days(modified, self.config.retention_days);
+            if active {
+                self.active.insert(path.clone());
+            } else if is_safe_to_delete(self.orphaned.contains(path), stale, active, self.is_protected(path)) {
+                self.safe.insert(path.clone());
+            }
+        }
+    }
+
+    async fn load_cleanup_targets(&mut self) {
+        match plan_cleanup(&self.logger.pool, &self.config).await {
+            Ok(plan) => self.expired = plan.targets().cloned().collect(),
+            Err(e) => self.logs.lock().unwrap().push(format!("Could not work out the cleanup targets: {}", e)),
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        if self.deleting.is_some() {
+            self.popup_state = PopupState::Info { message: "Another deletion is still running.".to_string() };
+            return;
+        }
+        if self.is_protected(&path) {
+            self.popup_state = PopupState::Info { message: self.protected_notice() };
+            return;
+        }
+        if !self.pre_delete_hook_allows(&path) {
+            self.popup_state = PopupState::Error { message: "Deletion cancelled by pre_delete_hook (see Logs).".to_string() };
+            return;
+        }
+        // Try without a password first (directly, then sudo -n / doas -n / pkexec)
+        self.start_deletion(path, None, false);
+    }
+
+    fn start_deletion(&mut self, path: String, password: Option<String>, subdirectory: bool) {
+        // Large trees take a while to remove; run it off the UI thread and finish in finish_deletion
+        self.deleting = Some((path.clone(), Instant::now()));
+        self.popup_state = PopupState::new_progress(format!("Deleting {}...", path));
+        let tx = self.deletion_tx.clone();
+        let pool = self.logger.pool.clone();
+        self.spawn_tracked(format!("deletion of {}", path), TaskKind::Critical, async move {
+            let escalated = password.is_some();
+            let target = path.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                if is_docker_artifact(&target) {
+                    if prune_docker_artifact(&target) {
+                        Ok(())
+                    } else {
+                        Err(io::Error::other("docker prune failed - is the Docker daemon running?"))
+                    }
+                } else {
+                    delete_path(&target, password.as_deref())
+                }
+            })
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e.to_string())));
+            // Part of the deletion itself: the UI may be gone before it handles the outcome
+            if result.is_ok() && !subdirectory {
+                forget_deleted(&pool, &path).await;
+            }
+            let _ = tx.send(DeletionOutcome { path, result, escalated, subdirectory }).await;
+        });
+    }
+
+    async fn finish_deletion(&mut self, outcome: DeletionOutcome) {
+        self.deleting = None;
+        let DeletionOutcome { path, result, escalated, subdirectory } = outcome;
+        match result {
+            Ok(()) if subdirectory => {
+                // The parent artifact stays listed; its recorded size catches up on the next scan
+                self.logs.lock().unwrap().push(format!("Deleted subdirectory {}", path));
+                self.popup_state = PopupState::Info { message: format!("Deleted {}.", path) };
+            }
+            Ok(()) => {
+                // The deletion task already dropped its database rows
+                self.remove_from_list(&path);
+                let message = if is_docker_artifact(&path) { "Docker resources pruned." } else { "Artifact deleted." };
+                self.popup_state = PopupState::Info { message: message.to_string() };
+            }
+            Err(e) if !escalated && can_prompt_for_password() && e.kind() == std::io::ErrorKind::PermissionDenied => {
+                // Prompt for password
+                self.pending_action = Some(format!("{}:{}", if subdirectory { "delete_subdir" } else { "delete" }, path));
+                self.popup_state = PopupState::new_input("Enter sudo password".to_string(), "".to_string());
+            }
+            Err(e) => self.show_deletion_failures(vec![(path, describe_delete_error(&e))], None),
+        }
+    }
+
+    fn open_subdirectories(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        if is_docker_artifact(&path) {
+            self.popup_state = PopupState::Info { message: "Docker artifacts have no subdirectories.".to_string() };
+            return;
+        }
+        self.popup_state = PopupState::new_subdirectories(path.clone());
+        self.size_children(path, false);
+    }
+
+    fn open_size_tree(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        if is_docker_artifact(&path) {
+            self.popup_state = PopupState::Info { message: "Docker artifacts have no subdirectories.".to_string() };
+            return;
+        }
+        let size = self.artifact_size(&path);
+        self.popup_state = PopupState::new_size_tree(path.clone(), size.unwrap_or(0));
+        self.size_children(path, size.is_none());
+    }
+
+    fn size_children(&mut self, path: String, measure_root: bool) {
+        let tx = self.child_sizes_tx.clone();
+        self.spawn_tracked(format!("sizing {}", path), TaskKind::Cancellable, async move {
+            let outcome = tokio::task::spawn_blocking(move || {
+                let size = measure_root.then(|| calculate_dir_size(&path));
+                let children = subdirectory_sizes(&path);
+                ChildSizesOutcome { path, size, children }
+            })
+            .await;
+            if let Ok(outcome) = outcome {
+                let _ = tx.send(outcome).await;
+            }
+        });
+    }
+
+    fn finish_child_sizes(&mut self, outcome: ChildSizesOutcome) {
+        // Dropped if the popup was closed meanwhile
+        match &mut self.popup_state {
+            PopupState::Subdirectories { parent, entries, loading, .. } if *loading && *parent == outcome.path => {
+                *entries = outcome.children;
+                *loading = false;
+            }
+            PopupState::SizeTree { nodes, sizing, .. } if sizing.as_deref() == Some(outcome.path.as_str()) => {
+                *sizing = None;
+                if let Some(index) = nodes.iter().position(|node| node.path == outcome.path) {
+                    if let Some(size) = outcome.size {
+                        nodes[index].size = size;
+                    }
+                    expand_size_node(nodes, index, outcome.children);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn open_largest_files(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        if is_docker_artifact(&path) {
+            self.popup_state = PopupState::Info { message: "Docker artifacts have no files to preview.".to_string() };
+            return;
+        }
+        self.popup_state = PopupState::LargestFiles { parent: path.clone(), files: vec![], truncated: false, loading: true };
+        let tx = self.largest_files_tx.clone();
+        self.spawn_tracked(format!("largest files in {}", path), TaskKind::Cancellable, async move {
+            let outcome = tokio::task::spawn_blocking(move || {
+                let (files, truncated) = largest_files(&path, 10, LARGEST_FILES_WALK_LIMIT);
+                LargestFilesOutcome { path, files, truncated }
+            })
+            .await;
+            if let Ok(outcome) = outcome {
+                let _ = tx.send(outcome).await;
+            }
+        });
+    }
+
+    fn finish_largest_files(&mut self, outcome: LargestFilesOutcome) {
+        // Dropped if the popup was closed meanwhile
+        if let PopupState::LargestFiles { parent, files, truncated, loading } = &mut self.popup_state
+            && *loading
+            && *parent == outcome.path
+        {
+            *files = outcome.files;
+            *truncated = outcome.truncated;
+            *loading = false;
+        }
+    }
+
+    fn delete_subdirectory(&mut self, child: String, password: Option<&str>) {
+        if self.deleting.is_some() {
+            self.popup_state = PopupState::Info { message: "Another deletion is still running.".to_string() };
+            return;
+        }
+        if self.is_protected(&child) {
+            self.popup_state = PopupState::Info { message: self.protected_notice() };
+            return;
+        }
+        // The hook already approved this path before the sudo prompt
+        if password.is_none() && !self.pre_delete_hook_allows(&child) {
+            self.popup_state = PopupState::Error { message: "Deletion cancelled by pre_delete_hook (see Logs).".to_string() };
+            return;
+        }
+        self.start_deletion(child, password.map(str::to_string), true);
+    }
+
+    async fn exclude_selected(&mut self) {
+        let Some(selected) = self.selected_artifact() else {
+            return;
+        };
+        self.add_exclusion(normalize_path(&selected)).await;
+    }
+
+    fn confirm_exclude_type(&mut self) {
+        let Some(selected) = self.selected_artifact() else {
+            return;
+        };
+        let path = Path::new(&selected);
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()).filter(|_| !is_docker_artifact(&selected)) else {
+            self.popup_state = PopupState::Info { message: "Only directory artifacts can be excluded by type.".to_string() };
+            return;
+        };
+        // The scan path the artifact was found under, or its project directory when none matches
+        let scan_root = self
+            .scan_root_of(path)
+            .or_else(|| path.parent().and_then(|p| p.parent()).map(|p| p.display().to_string()))
+            .unwrap_or_else(|| ".".to_string());
+        self.popup_state = PopupState::new_confirm_action(
+            format!("Exclude every {} under {}?", name, scan_root),
+            format!("exclude_type:{}", type_exclusion(&scan_root, &name)),
+        );
+    }
+
+    fn confirm_exclude_area(&mut self) {
+        let Some(selected) = self.selected_artifact() else {
+            return;
+        };
+        if is_docker_artifact(&selected) {
+            self.popup_state = PopupState::Info {
+                message: format!("Docker artifacts aren't under a scan path; press {} to exclude just this one.", self.keymap.label(Action::Exclude)),
+            };
+            return;
+        }
+        let Some(scan_root) = self.scan_root_of(Path::new(&selected)) else {
+            self.popup_state = PopupState::Info { message: format!("{} is not under any scan path.", selected) };
+            return;
+        };
+        let Some(area) = area_exclusion(&selected, &scan_root) else {
+            self.popup_state = PopupState::Info {
+                message: format!("{} sits directly in the scan path {}; press {} to exclude just it.", selected, scan_root, self.keymap.label(Action::Exclude)),
+            };
+            return;
+        };
+        let listed = self.artifacts.iter().filter(|a| Path::new(a).starts_with(&area)).count();
+        let noun = if listed == 1 { "artifact" } else { "artifacts" };
+        self.popup_state = PopupState::new_confirm_action(
+            format!("Exclude everything under {} ({} listed {})?", area, listed, noun),
+            format!("exclude_area:{}", area),
+        );
+    }
+
+    /// The deepest configured scan path containing `path`.
+    fn scan_root_of(&self, path: &Path) -> Option<String> {
+        self.config
+            .scan_paths
+            .iter()
+            .map(|p| normalize_path(p))
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.len())
+    }
+
+    async fn add_exclusion(&mut self, entry: String) {
+        if !self.config.excluded_paths.contains(&entry) {
+            self.record_undo(format!("exclude {}", entry));
+            self.config.excluded_paths.push(entry.clone());
+        }
+        self.save_config();
+        let newly_excluded: Vec<String> = self
+            .artifacts
+            .iter()
+            .filter(|a| is_path_excluded(a, std::slice::from_ref(&entry)))
+            .cloned()
+            .collect();
+        for path in newly_excluded {
+            self.remove_from_list(&path);
+        }
+        self.popup_state = PopupState::Info { message: "Path added to exclusion list. Rescanning...".to_string() };
+        if !self.scanning {
+            self.trigger_scan().await;
+        }
+    }
+
+    fn format_history(&mut self) {
+        self.build_history = self
+            .history_rows
+            .iter()
+            .map(|(project, language, time)| {
+                let time = if self.absolute_times {
+                    time.format("%Y-%m-%d %H:%M").to_string()
+                } else {
+                    format_relative_time(*time)
+                };
+                format!("{} - {} - {}", project, language, time)
+            })
+            .collect();
+    }
+
+    fn toggle_time_format(&mut self) {
+        self.absolute_times = !self.absolute_times;
+        self.format_history();
+    }
+
+    async fn reconnect_database(&mut self) {
+        // A fresh pool after Postgres was restarted; the old one only hands out dead connections
+        match BuildLogger::new(&self.config.database_url, &PoolSettings::from(&self.config)).await {
+            Ok(mut logger) => {
+                if let Err(e) = logger.set_dedupe(self.config.dedupe_builds).await {
+                    log_to_file(&format!("dedupe_builds could not be applied: {}", e));
+                }
+                self.logger.pool.close().await;
+                self.logger = logger;
+                self.db_healthy.store(true, Ordering::SeqCst);
+                // The listed artifacts stay; load_artifacts pages past them, so it isn't a reload
+                self.load_notes().await;
+                self.load_history().await;
+                log_to_file("Reconnected to the database");
+                self.popup_state = PopupState::Info { message: "Reconnected to the database.".to_string() };
+            }
+            Err(e) => {
+                log_to_file(&format!("Database reconnect failed: {}", e));
+                self.popup_state = PopupState::Error {
+                    message: format!("Could not reconnect to the database:\n\n{}\n\nPress {} to try again.", e, self.keymap.label(Action::Reconnect)),
+                };
+            }
+        }
+    }
+
+    fn refresh_sizes(&mut self) {
+        if self.scanning || self.refreshing.is_some() {
+            self.popup_state = PopupState::Info { message: "A scan or size refresh is already running.".to_string() };
+            return;
+        }
+        self.refreshing = Some(Instant::now());
+        self.popup_state = PopupState::new_progress("Refreshing sizes of known artifacts...".to_string());
+        let logger = self.logger.clone();
+        let roots: Vec<String> = self.config.scan_paths.iter().map(|p| normalize_path(p)).collect();
+        let tx = self.refresh_tx.clone();
+        self.spawn_tracked("size refresh", TaskKind::Cancellable, async move {
+            let started = Instant::now();
+            let outcome = match refresh_known_sizes(&logger, roots).await {
+                Ok((refreshed, removed)) => RefreshOutcome { refreshed, removed, error: None, elapsed: started.elapsed() },
+                Err(e) => RefreshOutcome { refreshed: 0, removed: vec![], error: Some(e.to_string()), elapsed: started.elapsed() },
+            };
+            let _ = tx.send(outcome).await;
+        });
+    }
+
+    async fn finish_refresh_sizes(&mut self, outcome: RefreshOutcome) {
+        self.refreshing = None;
+        let RefreshOutcome { refreshed, removed, error, elapsed } = outcome;
+        if let Some(error) = error {
+            self.popup_state = PopupState::Error { message: format!("Could not refresh sizes: {}", error) };
+            return;
+        }
+        for path in &removed {
+            self.remove_from_list(path);
+        }
+        self.load_history().await;
+        let mut message = format!("Refreshed the sizes of {} artifacts in {}.", refreshed, format_scan_duration(elapsed));
+        if !removed.is_empty() {
+            message.push_str(&format!("\nDropped {} that no longer exist on disk.", removed.len()));
+        }
+        self.logs.lock().unwrap().push(message.replace('\n', " "));
+        self.popup_state = PopupState::Info { message };
+    }
+
+    async fn reconcile_builds(&mut self) {
+        // Drop rows for artifacts deleted outside ratifact or by a run that was killed mid-deletion
+        let paths = match fetch_artifact_paths(&self.logger.pool).await {
+            Ok(paths) => paths.into_iter().filter(|p| !is_docker_artifact(p)).collect::<Vec<_>>(),
+            Err(e) => {
+                log_to_file(&format!("Startup reconciliation skipped: {}", e));
+                return;
+            }
+        };
+        let roots: Vec<String> = self.config.scan_paths.iter().map(|p| normalize_path(p)).collect();
+        let vanished = tokio::task::spawn_blocking(move || vanished_artifacts(&paths, &roots))
+            .await
+            .unwrap_or_default();
+        if vanished.is_empty() {
+            return;
+        }
+        let message = match delete_builds_for_paths(&self.logger.pool, &vanished).await {
+            Ok(rows) => format!("Startup check: removed {} build records of {} artifacts no longer on disk", rows, vanished.len()),
+            Err(e) => format!("Startup check: could not remove records of {} vanished artifacts: {}", vanished.len(), e),
+        };
+        log_to_file(&message);
+        self.logs.lock().unwrap().push(message);
+    }
+
+    async fn load_artifacts(&mut self) {
+        // Query DB for recent artifact paths, one batch after what is already listed
+        let limit = self.config.artifacts_load_limit.max(1);
+        match sqlx::query("SELECT artifact_path FROM builds GROUP BY artifact_path ORDER BY MAX(build_time) DESC, artifact_path LIMIT $1 OFFSET $2")
+            .bind(limit as i64)
+            .bind(self.artifacts.len() as i64)
+            .fetch_all(&self.logger.pool)
+            .await
+        {
+            Ok(rows) => {
+                self.more_artifacts = rows.len() == limit;
+                let roots: Vec<String> = self.config.scan_paths.iter().map(|p| normalize_path(p)).collect();
+                let rows: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+                for path in unlisted_paths(rows, &self.artifacts) {
+                    if !is_docker_artifact(&path)
+                        && let Some(project) = Path::new(&path).parent()
+                        && is_orphaned(&path_to_string(project))
+                    {
+                        self.orphaned.insert(path.clone());
+                    }
+                    if is_virtualenv(&path_from_string(&path)) {
+                        self.virtualenvs.insert(path.clone());
+                    }
+                    if let Some(root) = origin_of(&path, &roots) {
+                        self.origins.insert(path.clone(), root);
+                    }
+                    self.artifacts.push(path.clone());
+                }
+            }
+            Err(_) => {
+                // Ignore errors, start empty
+            }
+        }
+        if self.artifact_filter == ArtifactFilter::Expired {
+            self.load_cleanup_targets().await;
+        }
+        self.load_notes().await;
+    }
+
+    async fn load_more_artifacts(&mut self) {
+        if !self.more_artifacts {
+            self.popup_state = PopupState::Info { message: "Every recorded artifact is already listed.".to_string() };
+            return;
+        }
+        let before = self.artifacts.len();
+        self.load_artifacts().await;
+        // Sizes and chart entries only cover listed artifacts
+        self.load_history().await;
+        self.logs.lock().unwrap().push(format!("Loaded {} more artifacts from the database", self.artifacts.len() - before));
+    }
+
+    async fn load_notes(&mut self) {
+        if let Ok(notes) = crate::db::schema::fetch_notes(&self.logger.pool).await {
+            self.notes = notes;
+        }
+    }
+
+    fn export_cleanup_script(&mut self, target: &str) {
+        // Same set Clear All would delete; Docker entries are not paths and can't be rm'd
+        let paths: Vec<String> = self
+            .artifact_entries()
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| !is_docker_artifact(path))
+            .collect();
+        self.popup_state = match write_cleanup_script(Path::new(target), &paths) {
+            Ok(()) => PopupState::Info { message: format!("Wrote {} rm -rf lines to {}. Review it before running.", paths.len(), target) },
+            Err(e) => PopupState::Error { message: format!("Could not write {}: {}", target, e) },
+        };
+    }
+
+    fn edit_note(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        let current = self.notes.get(&path).cloned().unwrap_or_default();
+        self.note_target = Some(path);
+        self.popup_state = PopupState::new_input("Artifact Note".to_string(), current);
+    }
+
+    async fn save_note(&mut self, note: String) {
+        let Some(path) = self.note_target.take() else {
+            return;
+        };
+        match self.logger.set_note(&path, &note).await {
+            Ok(()) if note.trim().is_empty() => {
+                self.notes.remove(&path);
+            }
+            Ok(()) => {
+                self.notes.insert(path, note.trim().to_string());
+            }
+            Err(e) => {
+                self.popup_state = PopupState::Error { message: format!("Failed to save note: {}", e) };
+            }
+        }
+    }
+
+    async fn open_scan_diff(&mut self) {
+        match crate::db::schema::fetch_scan_diff(&self.logger.pool).await {
+            Ok(entries) => {
+                let lines = entries.iter().filter_map(|e| e.describe()).collect();
+                self.popup_state = PopupState::new_scan_diff(lines);
+            }
+            Err(_) => {
+                self.popup_state = PopupState::Error { message: "Failed to load scan diff.".to_string() };
+            }
+        }
+    }
+
+    async fn open_language_usage(&mut self) {
+        match crate::db::schema::fetch_language_aggregates(&self.logger.pool).await {
+            Ok(aggregates) => {
+                let lines = aggregates.iter().map(|a| a.describe()).collect();
+                self.popup_state = PopupState::LanguageUsage { lines };
+            }
+            Err(_) => {
+                self.popup_state = PopupState::Error { message: "Failed to load language usage.".to_string() };
+            }
+        }
+    }
+
+    fn open_duplicates(&mut self) {
+        // Reads one lockfile per node_modules, so only done on request
+        let fingerprints: Vec<(String, u64)> = self
+            .artifacts
+            .iter()
+            .filter_map(|path| dependency_fingerprint(path).map(|fingerprint| (path.clone(), fingerprint)))
+            .collect();
+        let sizes: HashMap<&str, u64> = self.chart_data.iter().map(|(path, size)| (path.as_str(), *size)).collect();
+        self.popup_state = PopupState::Duplicates { lines: duplicate_lines(duplicate_groups(&fingerprints), &sizes), selected: 0 };
+    }
+
+    async fn load_history(&mut self) {
+        // Query DB for build history
+        match sqlx::query("SELECT project_path, language, build_time FROM builds ORDER BY build_time DESC LIMIT $1")
+            .bind(self.config.history_load_limit.max(1) as i64)
+            .fetch_all(&self.logger.pool)
+            .await
+        {
+            Ok(rows) => {
+                self.history_rows = rows.into_iter().map(|row| (row.get(0), row.get(1), row.get(2))).collect();
+                self.format_history();
+            }
+            Err(_) => {
+                self.history_rows.clear();
+                self.build_history = vec![format!("Failed to load history ({} to reconnect)", self.keymap.label(Action::Reconnect))];
+            }
+        }
+        match sqlx::query("SELECT COUNT(*) FROM builds")
+            .fetch_one(&self.logger.pool)
+            .await
+        {
+            Ok(row) => {
+                self.total_builds = row.get::<i64, _>(0) as usize;
             }
             Err(_) => {
                 self.total_builds = 0;
             }
         }
+        self.age_buckets = fetch_age_buckets(&self.logger.pool).await.unwrap_or_default();
+        self.scan_durations = fetch_scan_durations(&self.logger.pool, SCAN_RUNS_SHOWN).await.unwrap_or_default();
         match sqlx::query("SELECT artifact_path, MAX(size_bytes) as size FROM builds GROUP BY artifact_path ORDER BY size DESC")
             .fetch_all(&self.logger.pool)
             .await
@@ -756,6 +2143,181 @@ impl App {
                 self.chart_data = vec![];
             }
         }
+        self.chart_data.extend(self.docker_sizes.iter().filter(|(path, _)| self.artifacts.contains(path)).cloned());
+        self.chart_data.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        // The chart shows each artifact's peak; a budget is about what it takes up now
+        let listed: HashSet<&str> = self.artifacts.iter().map(String::as_str).collect();
+        self.latest_sizes = crate::db::schema::fetch_latest_sizes(&self.logger.pool)
+            .await
+            .map(|sizes| sizes.into_iter().filter(|(path, _)| listed.contains(path.as_str())).collect())
+            .unwrap_or_default();
+        self.load_chart_trends().await;
+    }
+
+    fn visible_chart_data(&self) -> Vec<(String, u64)> {
+        // `chart_selected` indexes into this list
+        self.chart_data
+            .iter()
+            .filter(|(path, size)| *size >= self.chart_min_size && (self.chart_range.is_none() || self.chart_trends.contains_key(path)))
+            .cloned()
+            .collect()
+    }
+
+    fn charts_title(&self) -> String {
+        let mut parts = vec![];
+        if self.chart_min_size > 0 {
+            parts.push(format!("≥ {}, +/-", format_bytes(self.chart_min_size)));
+        }
+        if let Some(days) = self.chart_range {
+            parts.push(format!("growth over last {}d, T", days));
+        }
+        if parts.is_empty() {
+            "📊 Charts".to_string()
+        } else {
+            format!("📊 Charts ({})", parts.join(" | "))
+        }
+    }
+
+    async fn cycle_chart_range(&mut self) {
+        self.chart_range = next_chart_range(self.chart_range);
+        self.load_chart_trends().await;
+        self.chart_selected = self.chart_selected.min(self.visible_chart_data().len().saturating_sub(1));
+    }
+
+    async fn load_chart_trends(&mut self) {
+        self.chart_trends = match self.chart_range {
+            Some(days) => match fetch_size_trends(&self.logger.pool, days).await {
+                Ok(trends) => trends.into_iter().map(|t| (t.artifact_path.clone(), t.growth())).collect(),
+                Err(e) => {
+                    log_to_file(&format!("Failed to load size trends: {}", e));
+                    HashMap::new()
+                }
+            },
+            None => HashMap::new(),
+        };
+    }
+
+    fn adjust_chart_threshold(&mut self, raise: bool) {
+        self.chart_min_size = step_chart_threshold(self.chart_min_size, raise);
+        self.chart_selected = self.chart_selected.min(self.visible_chart_data().len().saturating_sub(1));
+    }
+
+    fn artifacts_title(&self) -> String {
+        let visible = self.visible_artifacts();
+        let size = visible.iter().filter_map(|a| self.artifact_size(a)).sum();
+        let filter = match self.artifact_filter {
+            ArtifactFilter::All => None,
+            ArtifactFilter::Orphaned => Some("👻 orphaned only"),
+            ArtifactFilter::Expired => Some("⏳ past retention only"),
+            ArtifactFilter::Safe => Some("✅ safe to delete only"),
+            ArtifactFilter::Active => Some("🔨 active only"),
+        };
+        let mut title = artifacts_title(visible.len(), self.artifacts.len(), size, filter);
+        if let marked @ 1.. = self.marked_visible().len() {
+            title.push_str(&format!(" - {} marked", marked));
+        }
+        if self.more_artifacts {
+            title.push_str(&format!(" - more recorded, {}: load more", self.keymap.label(Action::LoadMore)));
+        }
+        title
+    }
+
+    fn artifact_size(&self, path: &str) -> Option<u64> {
+        // Sizes cached from the last history load
+        self.chart_data.iter().find(|(p, _)| p == path).map(|(_, size)| *size)
+    }
+
+    fn is_oversized(&self, size: u64) -> bool {
+        self.config.alert_size_bytes > 0 && size >= self.config.alert_size_bytes
+    }
+
+    fn clear_all_targets(&self) -> Vec<String> {
+        // Clear All acts on what is on screen, so an active filter narrows it, and marks narrow it further
+        let marked = self.marked_visible();
+        if marked.is_empty() {
+            self.visible_artifacts().into_iter().cloned().collect()
+        } else {
+            marked
+        }
+    }
+
+    fn artifact_entries(&self) -> Vec<(String, u64)> {
+        self.clear_all_targets()
+            .into_iter()
+            .filter(|path| !self.is_protected(path))
+            .map(|path| {
+                let size = self.artifact_size(&path).unwrap_or(0);
+                (path, size)
+            })
+            .collect()
+    }
+
+    fn request_delete(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        // The size from the last history load; walking the tree here would hold up the dialog
+        let size = self.artifact_size(&path);
+        if needs_delete_confirmation(self.config.confirm_deletions, size, self.config.force_confirm_above_bytes) {
+            self.popup_state = PopupState::new_confirm_action(self.delete_prompt(&path, size), "delete".to_string());
+        } else {
+            self.delete_selected();
+        }
+    }
+
+    fn delete_prompt(&self, path: &str, size: Option<u64>) -> String {
+        match size {
+            _ if is_docker_artifact(path) => format!("Prune Docker resources ({})?", path),
+            None => "Delete this artifact (size unknown)?".to_string(),
+            Some(size) if self.config.force_confirm_above_bytes > 0 && size > self.config.force_confirm_above_bytes => {
+                format!("⚠️ Delete this artifact? It is {} on disk.", format_bytes(size))
+            }
+            Some(size) => format!("Delete this artifact ({})?", format_bytes(size)),
+        }
+    }
+
+    fn help_text(&self) -> String {
+        let k = |action| self.keymap.label(action);
+        let (nav, history, logs) = if self.config.vim_keys {
+            // Vim mode turns H and L into h and l
+            ("j/k ↑↓: Move | h/l Shift+Tab/Tab: Switch panel", "H".to_string(), "L".to_string())
+        } else {
+            ("↑↓: Move | Tab/Shift+Tab: Switch panel", k(Action::History), k(Action::Logs))
+        };
+        [
+            nav.to_string(),
+            "Ctrl+←→: Narrow/widen the focused panel | Ctrl+↑↓: Make it taller/shorter".to_string(),
+            "Enter: Artifact actions / open settings".to_string(),
+            format!(
+                "{}: Mark | {}: Mark all visible | {}: Invert marks | Esc: Clear filter, then marks | {}/{}: Delete marked",
+                k(Action::Mark), k(Action::MarkAll), k(Action::InvertMarks), k(Action::Delete), k(Action::ClearAll)
+            ),
+            format!(
+                "{}: Scan | {}: Refresh sizes only | {}: Load more from the database | {}: Delete | {}: Exclude | {}: Exclude all of this type | {}: Exclude its whole area of the scan path | {}: Protect | {}: Note | {}: Rebuild | {}: Copy path | {}: Orphans only | {}: Due for cleanup only | {}: Safe to delete / active / all | {}: Show excluded ({} on one: un-exclude) | {}: Export rm script",
+                k(Action::Scan), k(Action::RefreshSizes), k(Action::LoadMore), k(Action::Delete), k(Action::Exclude), k(Action::ExcludeType), k(Action::ExcludeArea), k(Action::Protect),
+                k(Action::Note), k(Action::Rebuild), k(Action::CopyPath), k(Action::Orphans), k(Action::ExpiredFilter), k(Action::CategoryFilter), k(Action::ShowExcluded), k(Action::Exclude), k(Action::ExportScript)
+            ),
+            format!(
+                "Charts panel: +/- Raise/lower the minimum size shown | {}: Size bars / age histogram | {}: Growth over 7/30/90 days",
+                k(Action::ChartAge), k(Action::ChartRange)
+            ),
+            format!(
+                "{}: Compare scans | {}: Usage by language | {}: Duplicate node_modules | {}: Reload history | {}: Relative/absolute times | {}: Relative/absolute paths | {}: Reconnect DB | {}: Logs",
+                k(Action::Compare), k(Action::Usage), k(Action::Duplicates), history, k(Action::ToggleTimes), k(Action::TogglePaths), k(Action::Reconnect), logs
+            ),
+            format!(
+                "Ctrl+Z: Undo exclude/protect | Ctrl+R: Redo | {}: Snooze automatic removal | {}: Mark artifacts over size budget",
+                k(Action::SnoozeCleanup), k(Action::BudgetPlan)
+            ),
+            format!(
+                "{}: Toggle file watching | {}: Toggle automatic removal | {}: Show/hide footer | {}: Settings | {}: Clear all | {}: Help | {}: About | {}: Quit",
+                k(Action::ToggleWatcher), k(Action::ToggleRemoval), k(Action::ToggleFooter), k(Action::Settings),
+                k(Action::ClearAll), k(Action::Help), k(Action::About), k(Action::Quit)
+            ),
+            String::new(),
+            format!("Vim keys: {} (toggle in Settings)", if self.config.vim_keys { "on" } else { "off" }),
+        ]
+        .join("\n")
     }
 
     fn mask_db_url(url: &str) -> String {
@@ -771,122 +2333,682 @@ impl App {
         }
     }
 
-    fn rebuild_selected(&mut self) {
-        if self.artifacts.is_empty() {
+    /// The selected artifact's project and the allowed command that would rebuild it.
+    fn rebuild_plan(&self) -> Result<(PathBuf, &'static str), String> {
+        let artifact_path = self.selected_artifact().ok_or("No artifact selected")?;
+        let project_root = path_from_string(&artifact_path).parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+        let command = rebuild_command(&project_root)
+            .ok_or_else(|| format!("No Cargo.toml or package.json in {}, so there is nothing to rebuild.", project_root.display()))?;
+        if !self.config.rebuild_commands.iter().any(|allowed| allowed.trim() == command) {
+            return Err(format!("'{}' is not in rebuild_commands in the config, so ratifact won't run it.", command));
+        }
+        Ok((project_root, command))
+    }
+
+    fn request_rebuild(&mut self) {
+        if self.rebuilding.is_some() {
+            self.popup_state = PopupState::Info { message: "A rebuild is still running.".to_string() };
             return;
         }
-        let artifact_path = &self.artifacts[self.selected];
-        let project_root = std::path::Path::new(artifact_path)
-            .parent()
-            .unwrap_or(std::path::Path::new("."));
-        // Detect build system
-        if project_root.join("Cargo.toml").exists() {
-            std::process::Command::new("sh")
-                .arg("-c")
-                .arg("cargo build")
-                .current_dir(project_root)
-                .spawn()
-                .ok(); // Fire and forget
-        } else if project_root.join("package.json").exists() {
-            std::process::Command::new("sh")
+        // Downloaded projects can run anything from a build script, so show exactly what will run first
+        self.popup_state = match self.rebuild_plan() {
+            Ok((project_root, command)) => {
+                let scripts = npm_build_scripts(&project_root);
+                let script = if command == "npm run build" && !scripts.is_empty() {
+                    let lines: Vec<String> = scripts.iter().map(|(name, script)| format!("  {}: {}", name, script)).collect();
+                    format!("\n\npackage.json runs these scripts, in order:\n{}", lines.join("\n"))
+                } else {
+                    String::new()
+                };
+                let message = format!("Run this in {}?\n\n  $ {}{}", project_root.display(), command, script);
+                PopupState::new_confirm_action(message, "rebuild".to_string())
+            }
+            Err(message) => PopupState::Error { message },
+        };
+    }
+
+    fn rebuild_selected(&mut self) {
+        // Checked again: the selection or the config may have changed while the popup was open
+        let (project_root, command) = match self.rebuild_plan() {
+            Ok(plan) => plan,
+            Err(message) => {
+                self.popup_state = PopupState::Error { message };
+                return;
+            }
+        };
+        let Some(artifact) = self.selected_artifact() else {
+            return;
+        };
+        self.logs.lock().unwrap().push(format!("Rebuild: running '{}' in {}", command, project_root.display()));
+        self.rebuilding = Some((command, Instant::now()));
+        self.popup_state = PopupState::new_progress(format!("Rebuilding project ({})...", command));
+        // Builds can take minutes; the result comes back through rebuild_rx
+        let tx = self.rebuild_tx.clone();
+        self.spawn_tracked(format!("rebuild ({})", command), TaskKind::Cancellable, async move {
+            let started = Instant::now();
+            let result = tokio::process::Command::new("sh")
                 .arg("-c")
-                .arg("npm run build")
-                .current_dir(project_root)
-                .spawn()
-                .ok();
-        }
-        // Add more as needed
+                .arg(command)
+                .current_dir(&project_root)
+                .stdin(std::process::Stdio::null())
+                // Quitting aborts the task; take the build down with it
+                .kill_on_drop(true)
+                .output()
+                .await;
+            let _ = tx.send(RebuildOutcome { artifact, command, result, elapsed: started.elapsed() }).await;
+        });
     }
 
-    async fn clear_all_builds(&mut self) {
-        let mut failed_paths = vec![];
-        for path in self.artifacts.clone() {
-            if !self.delete_with_sudo(&path, None) {
-                failed_paths.push(path);
+    fn finish_rebuild(&mut self, outcome: RebuildOutcome) {
+        self.rebuilding = None;
+        let RebuildOutcome { artifact, command, result, elapsed } = outcome;
+        let took = format_scan_duration(elapsed);
+        self.popup_state = match result {
+            Ok(output) if output.status.success() => {
+                self.logs.lock().unwrap().push(format!("Rebuild: '{}' finished in {}", command, took));
+                let message = format!("✅ '{}' finished in {}.\n\nRe-scan the size of {}?", command, took, artifact);
+                PopupState::new_confirm_action(message, format!("rescan_size:{}", artifact))
+            }
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                self.logs.lock().unwrap().push(format!("Rebuild: '{}' failed ({}) after {}", command, output.status, took));
+                self.logs.lock().unwrap().extend(stderr.lines().map(|line| format!("  {}", line)));
+                PopupState::Error {
+                    message: format!("'{}' failed ({}) after {}.\n\n{}\n\nFull output in Logs.", command, output.status, took, output_tail(&stderr, 8)),
+                }
             }
+            Err(e) => PopupState::Error { message: format!("Could not start '{}': {}", command, e) },
+        };
+    }
+
+    async fn rescan_size(&mut self, artifact: String) {
+        let before = self.artifact_size(&artifact);
+        let path = artifact.clone();
+        let size = tokio::task::spawn_blocking(move || calculate_dir_size(&path)).await.unwrap_or(0);
+        let project = Path::new(&artifact).parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        let language = self.language_cache.lock().unwrap().detect(&project);
+        if let Err(e) = self.logger.log_build(&project, &language, &artifact, size).await {
+            self.popup_state = PopupState::Error { message: format!("Could not record the new size: {}", e) };
+            return;
         }
-        if failed_paths.is_empty() {
-            self.artifacts.clear();
-            let _ = sqlx::query("DELETE FROM builds").execute(&self.logger.pool).await;
-            self.load_history().await;
-            self.popup_state = PopupState::Info { message: "All builds cleared.".to_string() };
-        } else {
-            self.pending_failed_paths = failed_paths;
+        self.load_history().await;
+        let message = match before {
+            Some(before) => format!("{} is now {} (was {}).", artifact, format_bytes(size), format_bytes(before)),
+            None => format!("{} is now {}.", artifact, format_bytes(size)),
+        };
+        self.popup_state = PopupState::Info { message };
+    }
+
+    fn clear_all_builds(&mut self) {
+        if self.deleting.is_some() {
+            self.popup_state = PopupState::Info { message: "Another deletion is still running.".to_string() };
+            return;
+        }
+        let marked = !self.marked_visible().is_empty();
+        let targets: Vec<String> = self.clear_all_targets().into_iter().filter(|path| !self.is_protected(path)).collect();
+        self.start_clear_all(targets, None, marked, false);
+    }
+
+    /// Deletes `paths` off the UI thread like a single deletion, finishing in finish_clear_all_builds.
+    /// A retry skips the pre_delete_hook, which already approved those paths.
+    fn start_clear_all(&mut self, paths: Vec<String>, password: Option<String>, marked: bool, retry: bool) {
+        let label = format!("{} artifacts", paths.len());
+        self.deleting = Some((label.clone(), Instant::now()));
+        self.popup_state = PopupState::new_progress(format!("Deleting {}...", label));
+        let tx = self.clear_all_tx.clone();
+        let pool = self.logger.pool.clone();
+        let hook = self.config.pre_delete_hook.clone().filter(|hook| !retry && !hook.trim().is_empty());
+        self.spawn_tracked(format!("deletion of {}", label), TaskKind::Critical, async move {
+            let outcome = clear_artifacts(&pool, paths, password, hook).await;
+            let _ = tx.send(ClearAllOutcome { marked, retry, ..outcome }).await;
+        });
+    }
+
+    async fn finish_clear_all_builds(&mut self, outcome: ClearAllOutcome) {
+        self.deleting = None;
+        let ClearAllOutcome { removed, failed, vetoed, marked, retry } = outcome;
+        for (path, reason) in &vetoed {
+            self.logs.lock().unwrap().push(format!("Kept {}: {}", path, reason));
+        }
+        // Keep the list in step with what is already gone, even before asking for sudo
+        for path in &removed {
+            self.remove_from_list(path);
+        }
+        if failed.is_empty() {
+            self.finish_clear_all().await;
+            let message = if retry {
+                "All builds cleared successfully.".to_string()
+            } else if vetoed.is_empty() {
+                match self.artifact_filter {
+                    _ if marked => format!("Deleted {} marked artifacts.", removed.len()),
+                    ArtifactFilter::All => "All builds cleared.".to_string(),
+                    ArtifactFilter::Orphaned => format!("Cleared {} orphaned artifacts.", removed.len()),
+                    ArtifactFilter::Expired => format!("Cleared {} artifacts past retention.", removed.len()),
+                    ArtifactFilter::Safe => format!("Cleared {} safe-to-delete artifacts.", removed.len()),
+                    ArtifactFilter::Active => format!("Cleared {} active artifacts.", removed.len()),
+                }
+            } else {
+                format!("Cleared {} artifacts; pre_delete_hook kept {} (see Logs).", removed.len(), vetoed.len())
+            };
+            self.popup_state = PopupState::Info { message };
+        } else if !retry && can_prompt_for_password() {
+            self.pending_failed_paths = failed.into_iter().map(|(path, _)| path).collect();
             self.pending_action = Some("clear_all".to_string());
             self.popup_state = PopupState::new_input("Enter sudo password".to_string(), "".to_string());
+        } else {
+            self.load_history().await;
+            self.offer_clear_all_retry(failed);
         }
     }
 
-    fn delete_with_sudo(&self, path: &str, password: Option<&str>) -> bool {
-        use std::process::Command;
-        use std::process::Stdio;
-        let mut cmd = Command::new("sudo");
-        if password.is_some() {
-            cmd.arg("-S");
-        } else {
-            cmd.arg("-n");
-        }
-        cmd.arg("rm").arg("-rf").arg(path);
-
-        if let Some(pwd) = password {
-            // With password - suppress all output
-            cmd.stdin(Stdio::piped());
-            cmd.stdout(Stdio::null());
-            cmd.stderr(Stdio::null());
-            let mut child = match cmd.spawn() {
-                Ok(c) => c,
-                Err(_) => return false,
-            };
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                let _ = stdin.write_all(format!("{}\n", pwd).as_bytes());
-            }
-            match child.wait() {
-                Ok(status) => status.success(),
-                Err(_) => false,
+    fn pre_delete_hook_allows(&self, path: &str) -> bool {
+        let Some(hook) = self.config.pre_delete_hook.as_deref().filter(|h| !h.trim().is_empty()) else {
+            return true;
+        };
+        match run_pre_delete_hook(hook, path) {
+            Ok(()) => true,
+            Err(e) => {
+                self.logs.lock().unwrap().push(format!("Kept {}: {}", path, e));
+                false
             }
+        }
+    }
+
+    fn retry_clear_all(&mut self, password: Option<&str>) {
+        let failed_paths = std::mem::take(&mut self.pending_failed_paths);
+        self.start_clear_all(failed_paths, password.map(str::to_string), false, true);
+    }
+
+    async fn finish_clear_all(&mut self) {
+        // Rows of what was deleted are already gone; everything else keeps its history
+        self.selected = 0;
+        self.load_history().await;
+    }
+
+    fn is_protected(&self, path: &str) -> bool {
+        is_path_protected(path, &self.config.protected_paths)
+    }
+
+    fn protected_notice(&self) -> String {
+        format!("🔒 This artifact is protected. Press {} to unprotect it first.", self.keymap.label(Action::Protect))
+    }
+
+    fn toggle_protection(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        self.record_undo(format!("{} {}", if self.is_protected(&path) { "unprotect" } else { "protect" }, path));
+        let message = if self.is_protected(&path) {
+            // Drop whichever entries cover this artifact (the artifact itself or its project)
+            self.config.protected_paths.retain(|p| !std::path::Path::new(&path).starts_with(p));
+            format!("Protection removed from {}", path)
         } else {
-            // Without password (sudo -n) - suppress output since we expect it to fail
-            cmd.stdout(Stdio::null());
-            cmd.stderr(Stdio::null());
-            match cmd.status() {
-                Ok(status) => status.success(),
-                Err(_) => false,
+            self.config.protected_paths.push(normalize_path(&path));
+            format!("🔒 {} is now protected from deletion", path)
+        };
+        self.save_config();
+        self.popup_state = PopupState::Info { message };
+    }
+
+    fn list_snapshot(&self) -> ListSnapshot {
+        ListSnapshot {
+            excluded_paths: self.config.excluded_paths.clone(),
+            protected_paths: self.config.protected_paths.clone(),
+        }
+    }
+
+    fn record_undo(&mut self, label: String) {
+        let before = self.list_snapshot();
+        self.undo_history.record(label, before);
+    }
+
+    async fn undo(&mut self) {
+        let current = self.list_snapshot();
+        match self.undo_history.undo(current) {
+            Some((label, before)) => {
+                self.restore_snapshot(before, format!("Undid: {} (Ctrl+R to redo)", label)).await;
             }
+            None => self.popup_state = PopupState::Info { message: "Nothing to undo. Deletions are permanent and can't be undone.".to_string() },
+        }
+    }
+
+    async fn redo(&mut self) {
+        let current = self.list_snapshot();
+        match self.undo_history.redo(current) {
+            Some((label, after)) => self.restore_snapshot(after, format!("Redid: {}", label)).await,
+            None => self.popup_state = PopupState::Info { message: "Nothing to redo.".to_string() },
+        }
+    }
+
+    async fn restore_snapshot(&mut self, snapshot: ListSnapshot, message: String) {
+        let exclusions_changed = snapshot.excluded_paths != self.config.excluded_paths;
+        self.config.excluded_paths = snapshot.excluded_paths;
+        self.config.protected_paths = snapshot.protected_paths;
+        self.save_config();
+        log_to_file(&message);
+        self.popup_state = PopupState::Info { message };
+        // Un-excluded artifacts only come back with a fresh scan
+        if exclusions_changed && !self.scanning {
+            self.trigger_scan().await;
+        }
+    }
+
+    fn save_config(&self) {
+        match &self.saved_scan_paths {
+            Some(scan_paths) => save_config(&Config { scan_paths: scan_paths.clone(), ..self.config.clone() }),
+            None => save_config(&self.config),
+        }
+        .ok();
+    }
+
+    fn resize_focused_panel(&mut self, wider: i16, taller: i16) {
+        self.config.panel_layout = self.config.panel_layout.clamped();
+        self.config.panel_layout.resize(self.focused_panel, wider, taller);
+        self.save_config();
+    }
+
+    fn toggle_footer(&mut self) {
+        self.config.show_footer = !self.config.show_footer;
+        self.save_config();
+    }
+
+    fn toggle_absolute_paths(&mut self) {
+        self.config.absolute_paths = !self.config.absolute_paths;
+        self.save_config();
+    }
+
+    fn toggle_automatic_removal(&mut self) {
+        if !self.automatic_removal && self.read_only {
+            self.popup_state = PopupState::Info { message: READ_ONLY_MESSAGE.to_string() };
+        } else if !self.automatic_removal {
+            // Show warning when enabling automatic removal
+            let message = "⚠️  AUTOMATIC REMOVAL WILL DELETE OLD ARTIFACTS\n\nPlease verify your build directories in the list above.\nAny directories matching common build paths older than\nretention days will be permanently deleted.\n\nEnable automatic removal? (Enter: Yes, Esc: No)".to_string();
+            let action = "enable_automatic_removal".to_string();
+            self.popup_state = PopupState::ConfirmAction { message, action };
+        } else {
+            // Disabling is safe, just toggle
+            self.set_automatic_removal(false);
+            self.popup_state = PopupState::Info { message: "Automatic removal disabled.".to_string() };
         }
     }
 
-    fn delete_with_sudo_blocking(path: &str, password: Option<&str>) -> bool {
-        use std::process::Command;
-        use std::process::Stdio;
-        let mut cmd = Command::new("sudo");
-        if password.is_some() {
-            cmd.arg("-S");
+    fn cleanup_snooze_remaining(&self) -> Option<Duration> {
+        self.cleanup_snoozed_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn snooze_cleanup(&mut self, hours: &str) {
+        let Some(hours) = hours.trim().parse::<f64>().ok().filter(|h| (0.0..=MAX_SNOOZE_HOURS).contains(h)) else {
+            self.popup_state = PopupState::Error { message: format!("Enter a number of hours between 0 and {}.", MAX_SNOOZE_HOURS) };
+            return;
+        };
+        let message = if hours > 0.0 {
+            let duration = Duration::from_secs_f64(hours * 3600.0);
+            self.cleanup_snoozed_until = Some(Instant::now() + duration);
+            format!("💤 Automatic removal snoozed for {}.", format_duration_short(duration))
         } else {
-            cmd.arg("-n");
-        }
-        cmd.arg("rm").arg("-rf").arg(path);
-        cmd.stdout(Stdio::null());
-        cmd.stderr(Stdio::null());
-        if let Some(pwd) = password {
-            cmd.stdin(Stdio::piped());
-            let mut child = match cmd.spawn() {
-                Ok(c) => c,
-                Err(_) => return false,
-            };
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                let _ = stdin.write_all(format!("{}\n", pwd).as_bytes());
+            self.cleanup_snoozed_until = None;
+            "Snooze cleared; automatic removal runs after the next scan.".to_string()
+        };
+        log_to_file(&message);
+        self.popup_state = PopupState::Info { message };
+    }
+
+    fn set_automatic_removal(&mut self, enabled: bool) {
+        self.automatic_removal = enabled;
+        self.config.automatic_removal = enabled;
+        self.save_config();
+    }
+
+    fn toggle_watcher(&mut self) {
+        self.config.watcher_enabled = !self.config.watcher_enabled;
+        self.save_config();
+        let message = if self.config.watcher_enabled {
+            let mut limit_reached = false;
+            for path in self.artifacts.iter().filter(|p| !is_docker_artifact(p)) {
+                if let Err(e) = self.watcher.watch(path_from_string(path))
+                    && is_watch_limit_error(&e)
+                {
+                    limit_reached = true;
+                    break;
+                }
+            }
+            if limit_reached {
+                self.logs.lock().unwrap().push(WATCH_LIMIT_MESSAGE.to_string());
+                format!("File watching enabled ({} paths).\n{}", self.watcher.watch_count(), WATCH_LIMIT_MESSAGE)
+            } else {
+                format!("File watching enabled ({} paths).", self.watcher.watch_count())
+            }
+        } else {
+            // Release every inotify watch right away, not just on the next scan
+            self.watcher.unwatch_all();
+            "File watching disabled.".to_string()
+        };
+        self.popup_state = PopupState::Info { message };
+    }
+
+    fn yank_selected(&mut self) {
+        let Some(path) = self.selected_artifact() else {
+            return;
+        };
+        // The clipboard holds text, so a name that isn't UTF-8 is copied as close as it gets
+        let path = display_path(&path);
+        let copied = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(path.clone()));
+        let message = match copied {
+            Ok(()) => format!("Copied {}", path),
+            Err(e) => {
+                // Headless sessions (SSH, no display server) have no clipboard, so show the path instead
+                self.logs.lock().unwrap().push(format!("Clipboard unavailable ({}): {}", e, path));
+                format!("Clipboard unavailable, path:\n{}", path)
             }
-            match child.wait() {
-                Ok(status) => status.success(),
-                Err(_) => false,
+        };
+        self.popup_state = PopupState::Info { message };
+    }
+
+    fn remove_artifact(path: &str, password: Option<&str>) -> Result<(), String> {
+        if is_docker_artifact(path) {
+            if prune_docker_artifact(path) {
+                Ok(())
+            } else {
+                Err("docker prune failed - is the Docker daemon running?".to_string())
             }
         } else {
-            match cmd.status() {
-                Ok(status) => status.success(),
-                Err(_) => false,
+            delete_path(path, password).map_err(|e| describe_delete_error(&e))
+        }
+    }
+
+    fn offer_clear_all_retry(&mut self, failures: Vec<(String, String)>) {
+        self.pending_failed_paths = failures.iter().map(|(path, _)| path.clone()).collect();
+        self.show_deletion_failures(failures, Some("retry_clear_all".to_string()));
+    }
+
+    fn show_deletion_failures(&mut self, failures: Vec<(String, String)>, retry_action: Option<String>) {
+        let mut logs = self.logs.lock().unwrap();
+        for (path, reason) in &failures {
+            logs.push(format!("Could not delete {}: {}", path, reason));
+        }
+        drop(logs);
+        self.popup_state = PopupState::new_deletion_failures(failures, retry_action);
+    }
+}
+
+pub fn index_of_largest(chart_data: &[(String, u64)]) -> Option<usize> {
+    chart_data
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, (_, size))| *size)
+        .map(|(i, _)| i)
+}
+
+/// Bytes taken up by the artifacts under `root`.
+pub fn budget_usage(root: &str, sizes: &[(String, u64)]) -> u64 {
+    let root = normalize_path(root);
+    sizes.iter().filter(|(path, _)| Path::new(path).starts_with(&root)).map(|(_, size)| size).sum()
+}
+
+/// Text bar for the Summary; stays full once the budget is reached.
+pub fn budget_gauge(used: u64, budget: u64, width: usize) -> String {
+    let ratio = if budget == 0 { 1.0 } else { (used as f64 / budget as f64).min(1.0) };
+    let filled = ((ratio * width as f64).round() as usize).min(width);
+    format!("[{}{}]", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Popup lines for duplicate groups, most reclaimable first: a heading per group, then its
+/// paths indented by two spaces. Keeping one copy is the baseline, the rest could be shared.
+pub fn duplicate_lines(groups: Vec<Vec<String>>, sizes: &HashMap<&str, u64>) -> Vec<String> {
+    let mut groups: Vec<(u64, Vec<String>)> = groups
+        .into_iter()
+        .map(|paths| {
+            let total: u64 = paths.iter().map(|path| sizes.get(path.as_str()).copied().unwrap_or(0)).sum();
+            let largest = paths.iter().map(|path| sizes.get(path.as_str()).copied().unwrap_or(0)).max().unwrap_or(0);
+            (total - largest, paths)
+        })
+        .collect();
+    groups.sort_by_key(|(bytes, _)| std::cmp::Reverse(*bytes));
+    let reclaimable: u64 = groups.iter().map(|(bytes, _)| bytes).sum();
+    let mut lines = vec![];
+    for (bytes, paths) in groups.iter() {
+        lines.push(format!("{} identical node_modules, ~{} reclaimable", paths.len(), format_bytes(*bytes)));
+        lines.extend(paths.iter().map(|path| format!("  {}", path)));
+    }
+    if !lines.is_empty() {
+        lines.push(format!(
+            "Total ~{} reclaimable by moving these projects to pnpm, which hard-links one shared copy of each package: `pnpm import`, then delete node_modules and `pnpm install`",
+            format_bytes(reclaimable)
+        ));
+    }
+    lines
+}
+
+pub fn footer_visible(show_footer: bool, height: u16) -> bool {
+    // Very short terminals need that row for panel content; ? still lists every key
+    show_footer && height >= FOOTER_MIN_HEIGHT
+}
+
+pub fn is_compact_layout(area: Rect) -> bool {
+    // Below this the 3-column grid clips every panel
+    area.width < COMPACT_MIN_WIDTH || area.height < COMPACT_MIN_HEIGHT
+}
+
+/// Clear All's deletion: asks `hook` about each of `paths`, removes the approved ones from disk,
+/// then the rows of exactly those that went. `marked` and `retry` are left for the caller.
+pub async fn clear_artifacts(pool: &PgPool, paths: Vec<String>, password: Option<String>, hook: Option<String>) -> ClearAllOutcome {
+    let (removed, failed, vetoed) = tokio::task::spawn_blocking(move || {
+        let mut removed = vec![];
+        let mut failed = vec![];
+        let mut vetoed = vec![];
+        for path in paths {
+            if let Some(hook) = &hook
+                && let Err(reason) = run_pre_delete_hook(hook, &path)
+            {
+                vetoed.push((path, reason));
+                continue;
+            }
+            match App::remove_artifact(&path, password.as_deref()) {
+                Ok(()) => removed.push(path),
+                Err(reason) => failed.push((path, reason)),
             }
         }
+        (removed, failed, vetoed)
+    })
+    .await
+    .unwrap_or_default();
+    // Part of the deletion itself: the UI may be gone before it handles the outcome
+    if !removed.is_empty()
+        && let Err(e) = delete_builds_for_paths(pool, &removed).await
+    {
+        log_to_file(&format!("Deleted {} artifacts but could not remove their database rows: {}", removed.len(), e));
+    }
+    ClearAllOutcome { removed, failed, vetoed, marked: false, retry: false }
+}
+
+/// Re-sizes every artifact already in the database without walking for new ones, records the
+/// new sizes and drops the ones that vanished. Returns how many were sized and which were dropped.
+async fn refresh_known_sizes(logger: &BuildLogger, roots: Vec<String>) -> Result<(usize, Vec<String>), sqlx::Error> {
+    // Docker entries aren't directories; a scan refreshes those
+    let known: Vec<(String, String, String)> =
+        fetch_known_artifacts(&logger.pool).await?.into_iter().filter(|(_, _, path)| !is_docker_artifact(path)).collect();
+    let paths: Vec<String> = known.iter().map(|(_, _, path)| path.clone()).collect();
+    let removed = tokio::task::spawn_blocking(move || vanished_artifacts(&paths, &roots)).await.unwrap_or_default();
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_SIZINGS));
+    let mut sizings = JoinSet::new();
+    for (project_path, language, artifact_path) in known.into_iter().filter(|(_, _, path)| !removed.contains(path)) {
+        let semaphore = Arc::clone(&semaphore);
+        sizings.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let path = artifact_path.clone();
+            let size = tokio::task::spawn_blocking(move || calculate_dir_size(&path)).await.unwrap_or(0);
+            BuildRecord { project_path, language, artifact_path, size }
+        });
+    }
+    let mut records = vec![];
+    while let Some(result) = sizings.join_next().await {
+        if let Ok(record) = result {
+            records.push(record);
+        }
+    }
+    logger.log_builds_batch(&records).await?;
+    delete_builds_for_paths(&logger.pool, &removed).await?;
+    Ok((records.len(), removed))
+}
+
+/// Minimum artifact sizes the Charts panel steps through with +/-.
+const CHART_THRESHOLDS: [u64; 7] = [0, 10_000_000, 100_000_000, 500_000_000, 1_000_000_000, 5_000_000_000, 10_000_000_000];
+
+/// Look-back windows `T` cycles through in the Charts panel.
+const CHART_RANGES: [Option<u32>; 4] = [None, Some(7), Some(30), Some(90)];
+
+pub fn next_chart_range(current: Option<u32>) -> Option<u32> {
+    let index = CHART_RANGES.iter().position(|r| *r == current).unwrap_or(0);
+    CHART_RANGES[(index + 1) % CHART_RANGES.len()]
+}
+
+pub fn format_duration_short(duration: Duration) -> String {
+    // Rounded up so a snooze never shows "0m" while it is still active
+    let minutes = duration.as_secs().div_ceil(60);
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// "4.2s", plus the average of the earlier scans once there are a few to compare with.
+pub fn scan_duration_summary(durations: &[Duration]) -> Option<String> {
+    let (last, earlier) = durations.split_first()?;
+    if earlier.len() < 2 {
+        return Some(format_scan_duration(*last));
+    }
+    let average = earlier.iter().sum::<Duration>() / earlier.len() as u32;
+    // A scan that suddenly takes much longer usually means some directory exploded
+    let warning = if last.as_secs_f64() > average.as_secs_f64() * 1.5 && *last > Duration::from_secs(1) { " ⚠️ slower than usual" } else { "" };
+    Some(format!("{} (avg {} over {} before){}", format_scan_duration(*last), format_scan_duration(average), earlier.len(), warning))
+}
+
+/// The last `lines` non-empty lines of a command's output, where the actual error usually is.
+pub fn output_tail(output: &str, lines: usize) -> String {
+    let kept: Vec<&str> = output.lines().filter(|line| !line.trim().is_empty()).collect();
+    kept[kept.len().saturating_sub(lines)..].join("\n")
+}
+
+pub fn format_growth(delta: i64) -> String {
+    match delta.signum() {
+        1 => format!("↑{}", format_bytes(delta.unsigned_abs())),
+        -1 => format!("↓{}", format_bytes(delta.unsigned_abs())),
+        _ => "=".to_string(),
+    }
+}
+
+pub fn step_chart_threshold(current: u64, raise: bool) -> u64 {
+    if raise {
+        CHART_THRESHOLDS.iter().copied().find(|&t| t > current).unwrap_or(current)
+    } else {
+        CHART_THRESHOLDS.iter().copied().rev().find(|&t| t < current).unwrap_or(0)
+    }
+}
+
+pub fn origin_of(path: &str, roots: &[String]) -> Option<String> {
+    // The most specific root wins when scan paths are nested
+    roots
+        .iter()
+        .filter(|root| Path::new(path).starts_with(root))
+        .max_by_key(|root| root.len())
+        .cloned()
+}
+
+/// `rows` not already in `listed`, in order, so loading a page twice never lists a path twice.
+pub fn unlisted_paths(rows: Vec<String>, listed: &[String]) -> Vec<String> {
+    let mut seen: HashSet<&str> = listed.iter().map(String::as_str).collect();
+    let fresh: Vec<bool> = rows.iter().map(|path| seen.insert(path.as_str())).collect();
+    rows.into_iter().zip(fresh).filter_map(|(path, fresh)| fresh.then_some(path)).collect()
+}
+
+pub fn invert_marks(marked: &HashSet<String>, visible: &[String]) -> HashSet<String> {
+    // Only the visible rows flip; marks hidden by a filter stay as they are
+    let mut inverted = marked.clone();
+    for path in visible {
+        if !inverted.remove(path) {
+            inverted.insert(path.clone());
+        }
+    }
+    inverted
+}
+
+pub fn artifacts_title(visible: usize, total: usize, size: u64, filter: Option<&str>) -> String {
+    // Sizes come from the last history load, so artifacts found since then count as 0
+    match filter {
+        Some(filter) => format!("📦 Artifacts ({} of {}, {}, {})", visible, total, format_bytes(size), filter),
+        None => format!("📦 Artifacts ({}, {})", total, format_bytes(size)),
+    }
+}
+
+pub fn is_destructive_command(cmd: &PopupCommand) -> bool {
+    // Everything that deletes or rebuilds; --read-only turns these away before they run
+    match cmd {
+        PopupCommand::DeleteArtifact | PopupCommand::RebuildArtifact | PopupCommand::ClearAllBuilds => true,
+        PopupCommand::ConfirmAction { action } => {
+            action.starts_with("delete_subdir:")
+                || matches!(action.as_str(), "delete" | "rebuild" | "retry_clear_all" | "enable_automatic_removal")
+        }
+        _ => false,
+    }
+}
+
+pub fn artifacts_empty_message(scanning: bool, scanned: bool, total: usize, filter: ArtifactFilter, keymap: &KeyMap) -> String {
+    // Point at the next step instead of leaving an empty box
+    if scanning {
+        "Scanning... artifacts show up here when it finishes".to_string()
+    } else if total > 0 {
+        match filter {
+            ArtifactFilter::Orphaned => format!("No orphaned artifacts - press {} to show all", keymap.label(Action::Orphans)),
+            ArtifactFilter::Expired => format!("Nothing is due for automatic removal - press {} to show all", keymap.label(Action::ExpiredFilter)),
+            ArtifactFilter::Safe => format!("Nothing is clearly safe to delete - press {} for active ones", keymap.label(Action::CategoryFilter)),
+            ArtifactFilter::Active => format!("Nothing is being built right now - press {} to show all", keymap.label(Action::CategoryFilter)),
+            ArtifactFilter::All => "Nothing to show".to_string(),
+        }
+    } else if scanned {
+        format!(
+            "No artifacts found - press {} to change the Scan Path, then {} to rescan",
+            keymap.label(Action::Settings),
+            keymap.label(Action::Scan)
+        )
+    } else {
+        format!("No artifacts yet - press {} to scan", keymap.label(Action::Scan))
+    }
+}
+
+pub fn needs_delete_confirmation(confirm_deletions: bool, size: Option<u64>, force_confirm_above_bytes: u64) -> bool {
+    // Huge artifacts always ask, even with confirmations turned off, and so does one that might be
+    confirm_deletions || (force_confirm_above_bytes > 0 && size.is_none_or(|size| size > force_confirm_above_bytes))
+}
+
+pub fn step_index(current: usize, delta: isize, len: usize) -> usize {
+    // Move by delta, staying inside 0..len
+    let max = len.saturating_sub(1) as isize;
+    (current as isize + delta).clamp(0, max) as usize
+}
+
+pub fn vim_remap(code: KeyCode) -> KeyCode {
+    // hjkl navigate; history and logs move to H and L
+    match code {
+        KeyCode::Char('j') => KeyCode::Down,
+        KeyCode::Char('k') => KeyCode::Up,
+        KeyCode::Char('h') => KeyCode::BackTab,
+        KeyCode::Char('l') => KeyCode::Tab,
+        KeyCode::Char('H') => KeyCode::Char('h'),
+        KeyCode::Char('L') => KeyCode::Char('l'),
+        other => other,
+    }
+}
+
+fn strip_colors(f: &mut Frame) {
+    // Plain mode: default fg/bg everywhere; highlighted cells fall back to reverse video
+    for cell in f.buffer_mut().content.iter_mut() {
+        let highlighted = cell.bg != Color::Reset;
+        cell.set_fg(Color::Reset).set_bg(Color::Reset);
+        if highlighted {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
     }
 }
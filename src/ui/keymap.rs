@@ -0,0 +1,172 @@
+// Configurable key bindings for the main view
+
+use ratatui::crossterm::event::KeyCode;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Scan,
+    Delete,
+    ClearAll,
+    Exclude,
+    ExcludeType,
+    ExcludeArea,
+    Protect,
+    Note,
+    Rebuild,
+    CopyPath,
+    History,
+    ToggleTimes,
+    TogglePaths,
+    Compare,
+    Usage,
+    ToggleWatcher,
+    ToggleRemoval,
+    Orphans,
+    ExpiredFilter,
+    CategoryFilter,
+    ShowExcluded,
+    RefreshSizes,
+    LoadMore,
+    Settings,
+    Logs,
+    Help,
+    About,
+    ExportScript,
+    ToggleFooter,
+    Mark,
+    MarkAll,
+    InvertMarks,
+    Reconnect,
+    ChartAge,
+    ChartRange,
+    SnoozeCleanup,
+    BudgetPlan,
+    Duplicates,
+}
+
+/// Config name and default key of every bindable action.
+const ACTIONS: [(Action, &str, KeyCode); 39] = [
+    (Action::Quit, "quit", KeyCode::Char('q')),
+    (Action::Scan, "scan", KeyCode::Char('s')),
+    (Action::Delete, "delete", KeyCode::Char('d')),
+    (Action::ClearAll, "clear_all", KeyCode::Char('D')),
+    (Action::Exclude, "exclude", KeyCode::Char('x')),
+    (Action::ExcludeType, "exclude_type", KeyCode::Char('X')),
+    (Action::ExcludeArea, "exclude_area", KeyCode::Char('N')),
+    (Action::Protect, "protect", KeyCode::Char('P')),
+    (Action::Note, "note", KeyCode::Char('n')),
+    (Action::Rebuild, "rebuild", KeyCode::Char('r')),
+    (Action::CopyPath, "copy_path", KeyCode::Char('y')),
+    (Action::History, "history", KeyCode::Char('h')),
+    (Action::ToggleTimes, "toggle_times", KeyCode::Char('t')),
+    (Action::TogglePaths, "toggle_paths", KeyCode::Char('p')),
+    (Action::Compare, "compare", KeyCode::Char('c')),
+    (Action::Usage, "usage", KeyCode::Char('u')),
+    (Action::Duplicates, "duplicates", KeyCode::Char('U')),
+    (Action::ToggleWatcher, "toggle_watcher", KeyCode::Char('w')),
+    (Action::ToggleRemoval, "toggle_removal", KeyCode::Char('a')),
+    (Action::Orphans, "orphans", KeyCode::Char('o')),
+    (Action::ExpiredFilter, "expired_filter", KeyCode::Char('O')),
+    (Action::CategoryFilter, "category_filter", KeyCode::Char('C')),
+    (Action::ShowExcluded, "show_excluded", KeyCode::Char('B')),
+    (Action::RefreshSizes, "refresh_sizes", KeyCode::Char('R')),
+    (Action::LoadMore, "load_more", KeyCode::Char('M')),
+    (Action::Settings, "settings", KeyCode::Char('e')),
+    (Action::Logs, "logs", KeyCode::Char('l')),
+    (Action::Help, "help", KeyCode::Char('?')),
+    (Action::About, "about", KeyCode::Char('i')),
+    (Action::ExportScript, "export_script", KeyCode::Char('E')),
+    (Action::ToggleFooter, "toggle_footer", KeyCode::Char('F')),
+    (Action::Mark, "mark", KeyCode::Char(' ')),
+    (Action::MarkAll, "mark_all", KeyCode::Char('A')),
+    (Action::InvertMarks, "invert_marks", KeyCode::Char('I')),
+    (Action::Reconnect, "reconnect", KeyCode::F(5)),
+    (Action::ChartAge, "chart_age", KeyCode::Char('v')),
+    (Action::ChartRange, "chart_range", KeyCode::Char('T')),
+    (Action::SnoozeCleanup, "snooze_cleanup", KeyCode::Char('Z')),
+    (Action::BudgetPlan, "budget_plan", KeyCode::Char('b')),
+];
+
+// Chart threshold keys are handled before the key map looks anything up
+const RESERVED_KEYS: [char; 3] = ['+', '=', '-'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    keys: HashMap<Action, KeyCode>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap { keys: ACTIONS.iter().map(|(action, _, key)| (*action, *key)).collect() }
+    }
+}
+
+impl KeyMap {
+    /// Applies `[keybindings]` from the config on top of the defaults.
+    ///
+    /// Any problem (unknown action, bad key, two actions on one key) is reported and the
+    /// defaults are used instead, so a typo never leaves an action unreachable.
+    pub fn from_overrides(overrides: &BTreeMap<String, String>) -> (KeyMap, Vec<String>) {
+        let mut keymap = KeyMap::default();
+        let mut problems = vec![];
+        for (name, key) in overrides {
+            let Some((action, _, _)) = ACTIONS.iter().find(|(_, n, _)| n == name) else {
+                problems.push(format!("keybindings: unknown action '{}'", name));
+                continue;
+            };
+            match parse_key(key) {
+                Some(KeyCode::Char(c)) if RESERVED_KEYS.contains(&c) => {
+                    problems.push(format!("keybindings: '{}' is reserved for the Charts panel", key));
+                }
+                Some(code) => {
+                    keymap.keys.insert(*action, code);
+                }
+                None => problems.push(format!("keybindings: can't parse key '{}' for {}", key, name)),
+            }
+        }
+        let mut owners: HashMap<KeyCode, &str> = HashMap::new();
+        for (action, name, _) in ACTIONS.iter() {
+            let code = keymap.keys[action];
+            if let Some(other) = owners.insert(code, name) {
+                problems.push(format!("keybindings: '{}' is bound to both {} and {}", key_label(code), other, name));
+            }
+        }
+        if problems.is_empty() {
+            (keymap, problems)
+        } else {
+            problems.push("keybindings: using the default keys until these are fixed".to_string());
+            (KeyMap::default(), problems)
+        }
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.keys.iter().find(|(_, key)| **key == code).map(|(action, _)| *action)
+    }
+
+    pub fn label(&self, action: Action) -> String {
+        key_label(self.keys[&action])
+    }
+}
+
+pub fn parse_key(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyCode::Char(c)),
+        _ if key.eq_ignore_ascii_case("space") => Some(KeyCode::Char(' ')),
+        _ => {
+            let number = key.strip_prefix(['f', 'F'])?.parse::<u8>().ok()?;
+            (1..=12).contains(&number).then_some(KeyCode::F(number))
+        }
+    }
+}
+
+pub fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
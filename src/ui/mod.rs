@@ -1,4 +1,8 @@
 // UI module for Ratatui interface
 
 pub mod app;
+pub mod keymap;
+pub mod palette;
 pub mod popup;
+pub mod tasks;
+pub mod undo;
@@ -0,0 +1,62 @@
+// Colors for the chart, age histogram and artifact types
+
+use ratatui::style::Color;
+
+/// Every color the views pick by meaning rather than for layout.
+pub struct Palette {
+    /// Cycled through by the size chart.
+    pub chart: [Color; 7],
+    /// Oversized artifacts; kept out of `chart` so a warning never looks like an ordinary bar.
+    pub alert: Color,
+    /// Age histogram buckets, freshest first.
+    pub age: [Color; 4],
+    pub docker: Color,
+    pub virtualenv: Color,
+    pub target: Color,
+    pub node_modules: Color,
+    pub pycache: Color,
+    pub build: Color,
+    pub other: Color,
+}
+
+const DEFAULT: Palette = Palette {
+    chart: [Color::LightBlue, Color::Green, Color::Blue, Color::Yellow, Color::Magenta, Color::Cyan, Color::White],
+    alert: Color::Red,
+    age: [Color::Green, Color::Yellow, Color::LightRed, Color::Red],
+    docker: Color::Cyan,
+    virtualenv: Color::LightYellow,
+    target: Color::Green,
+    node_modules: Color::Blue,
+    pycache: Color::Yellow,
+    build: Color::Red,
+    other: Color::White,
+};
+
+// Okabe-Ito: distinguishable with the common kinds of color blindness, and without red/green pairs
+const ORANGE: Color = Color::Rgb(230, 159, 0);
+const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+const YELLOW: Color = Color::Rgb(240, 228, 66);
+const BLUE: Color = Color::Rgb(0, 114, 178);
+const VERMILLION: Color = Color::Rgb(213, 94, 0);
+const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+
+const COLOR_BLIND: Palette = Palette {
+    chart: [ORANGE, SKY_BLUE, BLUISH_GREEN, YELLOW, BLUE, REDDISH_PURPLE, Color::White],
+    alert: VERMILLION,
+    // Light to dark as well as blue to orange, so the order survives without hue
+    age: [SKY_BLUE, YELLOW, ORANGE, VERMILLION],
+    docker: SKY_BLUE,
+    virtualenv: YELLOW,
+    target: BLUISH_GREEN,
+    node_modules: BLUE,
+    pycache: ORANGE,
+    build: REDDISH_PURPLE,
+    other: Color::White,
+};
+
+impl Palette {
+    pub fn new(color_blind: bool) -> &'static Palette {
+        if color_blind { &COLOR_BLIND } else { &DEFAULT }
+    }
+}
@@ -4,10 +4,11 @@ use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph, Wrap},
     crossterm::event::KeyCode,
 };
+use crate::utils::{format_bytes, truncate_middle};
 
 pub enum PopupCommand {
     OpenInput { title: String, initial: String },
@@ -17,28 +18,118 @@ pub enum PopupCommand {
     DeleteArtifact,
     RebuildArtifact,
     ClearAllBuilds,
+    BrowseSubdirectories,
+    ShowLargestFiles,
+    ShowSizeTree,
+    /// Size a size tree node's children in the background, then expand it.
+    ExpandSizeNode { path: String },
     ConfirmAction { action: String },
     OpenExcludedPaths,
+    ToggleDocker,
+    ToggleVimKeys,
+}
+
+const SETTINGS_OPTIONS: [&str; 6] = ["Retention Days", "Scan Path", "Automatic Removal", "Excluded Paths", "Docker Cleanup", "Vim Keys"];
+
+/// The settings entry that matters most for each main panel, so `e` lands on it.
+pub fn settings_entry_for_panel(panel: usize) -> usize {
+    match panel {
+        // Artifacts: what gets listed
+        0 => 3,
+        // History: where scans look
+        1 => 1,
+        // Summary: the auto-cleanup status
+        4 => 2,
+        // Charts (age of builds) and the Settings panel itself
+        _ => 0,
+    }
+}
+
+/// Expanding stops here; deeper levels are rarely worth the walk in a large node_modules.
+pub const SIZE_TREE_MAX_DEPTH: usize = 8;
+/// Children listed per expanded directory, largest first.
+pub const SIZE_TREE_MAX_CHILDREN: usize = 50;
+/// Rows the whole tree may hold.
+pub const SIZE_TREE_MAX_NODES: usize = 1_000;
+
+/// One row of the size tree popup; children follow their parent, one level deeper.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeTreeNode {
+    pub path: String,
+    pub size: u64,
+    pub depth: usize,
+    pub expanded: bool,
+    /// Children left out by the caps, shown as a "+N more" hint.
+    pub omitted: usize,
+}
+
+/// Inserts `children` (already sized) below the node at `index` and marks it expanded.
+pub fn expand_size_node(nodes: &mut Vec<SizeTreeNode>, index: usize, mut children: Vec<(String, u64)>) {
+    let Some(node) = nodes.get(index) else {
+        return;
+    };
+    let depth = node.depth + 1;
+    children.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    let room = SIZE_TREE_MAX_CHILDREN.min(SIZE_TREE_MAX_NODES.saturating_sub(nodes.len()));
+    let omitted = children.len().saturating_sub(room);
+    let rows: Vec<SizeTreeNode> = children
+        .into_iter()
+        .take(room)
+        .map(|(path, size)| SizeTreeNode { path, size, depth, expanded: false, omitted: 0 })
+        .collect();
+    nodes.splice(index + 1..index + 1, rows);
+    nodes[index].expanded = true;
+    nodes[index].omitted = omitted;
+}
+
+/// Removes everything below the node at `index`.
+pub fn collapse_size_node(nodes: &mut Vec<SizeTreeNode>, index: usize) {
+    let Some(node) = nodes.get(index) else {
+        return;
+    };
+    let depth = node.depth;
+    let end = nodes[index + 1..].iter().position(|n| n.depth <= depth).map(|i| index + 1 + i).unwrap_or(nodes.len());
+    nodes.drain(index + 1..end);
+    nodes[index].expanded = false;
+    nodes[index].omitted = 0;
 }
 
 pub enum PopupState {
     None,
     SettingsList { selected: usize },
     Input { title: String, input: String },
-    DirBrowse { path: String, items: Vec<String>, selected: usize },
-    Logs { logs: std::sync::Arc<std::sync::Mutex<Vec<String>>> },
-    Scanning { logs: std::sync::Arc<std::sync::Mutex<Vec<String>>> },
+    DirBrowse { path: String, all_items: Vec<String>, items: Vec<String>, filter: String, selected: usize },
+    Logs { logs: std::sync::Arc<std::sync::Mutex<Vec<String>>>, scroll: usize },
+    Scanning { logs: std::sync::Arc<std::sync::Mutex<Vec<String>>>, scroll: usize },
     ArtifactActions { selected: usize },
-    ClearAllConfirmation,
+    ClearAllConfirmation { entries: Vec<(String, u64)>, scroll: u16, typed: String },
     ConfirmAction { message: String, action: String },
     Progress { message: String },
     Info { message: String },
+    /// Failures; drawn in red so they can't be mistaken for an Info success message.
+    Error { message: String },
     ExcludedPathsList { paths: Vec<String>, selected: usize },
+    ScanDiff { lines: Vec<String>, selected: usize },
+    Help { text: String },
+    LanguageUsage { lines: Vec<String> },
+    Duplicates { lines: Vec<String>, selected: usize },
+    /// `loading` until the background sizing of `parent`'s children comes back.
+    Subdirectories { parent: String, entries: Vec<(String, u64)>, selected: usize, loading: bool },
+    /// `loading` until the background walk of `parent` comes back.
+    LargestFiles { parent: String, files: Vec<(String, u64)>, truncated: bool, loading: bool },
+    /// `sizing` is the node whose children are being sized; one at a time.
+    SizeTree { nodes: Vec<SizeTreeNode>, selected: usize, sizing: Option<String> },
+    DeletionFailures { failures: Vec<(String, String)>, selected: usize, retry_action: Option<String> },
 }
 
 impl PopupState {
-    pub fn new_settings_list() -> Self {
-        PopupState::SettingsList { selected: 0 }
+    /// Popups where every printable key is text, so `q` must not quit.
+    pub fn takes_text(&self) -> bool {
+        matches!(self, PopupState::Input { .. } | PopupState::DirBrowse { .. } | PopupState::ClearAllConfirmation { .. })
+    }
+
+    pub fn new_settings_list(panel: usize) -> Self {
+        PopupState::SettingsList { selected: settings_entry_for_panel(panel) }
     }
 
     pub fn new_input(title: String, initial: String) -> Self {
@@ -47,20 +138,26 @@ impl PopupState {
 
     pub fn new_dir_browse() -> Self {
         let path = "/".to_string();
-        let items = get_dir_items(&path);
-        PopupState::DirBrowse { path, items, selected: 0 }
+        let all_items = get_dir_items(&path);
+        PopupState::DirBrowse { path, items: all_items.clone(), all_items, filter: String::new(), selected: 0 }
     }
 
     pub fn new_logs_popup(logs: std::sync::Arc<std::sync::Mutex<Vec<String>>>) -> Self {
-        PopupState::Logs { logs }
+        PopupState::Logs { logs, scroll: 0 }
+    }
+
+    pub fn new_scanning(logs: std::sync::Arc<std::sync::Mutex<Vec<String>>>) -> Self {
+        PopupState::Scanning { logs, scroll: 0 }
     }
 
     pub fn new_artifact_actions() -> Self {
         PopupState::ArtifactActions { selected: 0 }
     }
 
-    pub fn new_clear_all_confirmation() -> Self {
-        PopupState::ClearAllConfirmation
+    pub fn new_clear_all_confirmation(mut entries: Vec<(String, u64)>) -> Self {
+        // Largest first so the listed "top entries" are the ones that matter
+        entries.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        PopupState::ClearAllConfirmation { entries, scroll: 0, typed: String::new() }
     }
 
     pub fn new_confirm_action(message: String, action: String) -> Self {
@@ -74,6 +171,24 @@ impl PopupState {
     pub fn new_excluded_paths(paths: Vec<String>) -> Self {
         PopupState::ExcludedPathsList { paths, selected: 0 }
     }
+
+    pub fn new_scan_diff(lines: Vec<String>) -> Self {
+        PopupState::ScanDiff { lines, selected: 0 }
+    }
+
+    pub fn new_deletion_failures(failures: Vec<(String, String)>, retry_action: Option<String>) -> Self {
+        PopupState::DeletionFailures { failures, selected: 0, retry_action }
+    }
+
+    pub fn new_subdirectories(parent: String) -> Self {
+        PopupState::Subdirectories { parent, entries: vec![], selected: 0, loading: true }
+    }
+
+    /// Opens on the root alone, its first level still being sized.
+    pub fn new_size_tree(root: String, size: u64) -> Self {
+        let nodes = vec![SizeTreeNode { path: root.clone(), size, depth: 0, expanded: false, omitted: 0 }];
+        PopupState::SizeTree { nodes, selected: 0, sizing: Some(root) }
+    }
 }
 
 impl PopupState {
@@ -82,9 +197,8 @@ impl PopupState {
             PopupState::SettingsList { selected } => {
                 let popup_area = centered_rect(25, 30, area);
                 f.render_widget(Clear, popup_area);
-                let options = ["Retention Days", "Scan Path", "Automatic Removal", "Excluded Paths"];
                 let mut items = Vec::new();
-                for (i, &opt) in options.iter().enumerate() {
+                for (i, &opt) in SETTINGS_OPTIONS.iter().enumerate() {
                     let style = if i == *selected {
                         Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
                     } else {
@@ -110,54 +224,75 @@ impl PopupState {
                 f.render_widget(para, popup_area);
                 // Cursor not implemented simply
             }
-            PopupState::DirBrowse { path, items, selected } => {
+            PopupState::DirBrowse { path, items, filter, selected, .. } => {
                 let popup_area = centered_rect(50, 50, area);
                 f.render_widget(Clear, popup_area);
                 let list_items: Vec<ListItem> = items
                     .iter()
                     .map(|i| ListItem::new(i.as_str()))
                     .collect();
+                let title = if filter.is_empty() {
+                    format!("Browse: {} (Type to filter, ↑↓ Nav, Enter: Enter, Tab: Select, Space: Select Current, Esc: Cancel)", path)
+                } else {
+                    format!("Browse: {} [filter: {}] (Backspace: Widen, Enter: Enter, Tab: Select, Esc: Clear filter)", path, filter)
+                };
                 let list = List::new(list_items)
-                    .block(Block::default().title(format!("Browse: {} (↑↓ Nav, Enter: Enter, s: Select, Space: Select Current, Esc: Cancel)", path)).borders(Borders::ALL))
+                    .block(Block::default().title(title).borders(Borders::ALL))
                     .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
                 let mut state = ListState::default();
                 state.select(Some(*selected));
                 f.render_stateful_widget(list, popup_area, &mut state);
             }
-            PopupState::Logs { logs } => {
+            PopupState::Logs { logs, scroll } => {
                 let popup_area = centered_rect(60, 40, area);
                 f.render_widget(Clear, popup_area);
                 let logs_guard = logs.lock().unwrap();
-                let logs_text = logs_guard.iter().rev().take(20).cloned().collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n");
-                let para = Paragraph::new(logs_text).block(
-                    Block::default()
-                        .title("📝 Logs")
-                        .borders(Borders::ALL)
-                        .padding(Padding::new(1, 1, 1, 0)),
+                // Inner size: borders (2) + horizontal padding (2), top padding (1)
+                let window = visible_log_window(
+                    &logs_guard,
+                    popup_area.height.saturating_sub(3) as usize,
+                    popup_area.width.saturating_sub(4) as usize,
+                    *scroll,
                 );
+                let para = Paragraph::new(window.lines.join("\n"))
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::default()
+                            .title(format!("📝 Logs{} (↑↓ PgUp/PgDn Esc)", window.indicator()))
+                            .borders(Borders::ALL)
+                            .padding(Padding::new(1, 1, 1, 0)),
+                    );
                 f.render_widget(para, popup_area);
             }
-            PopupState::Scanning { logs } => {
+            PopupState::Scanning { logs, scroll } => {
                 let popup_area = centered_rect(60, 40, area);
                 f.render_widget(Clear, popup_area);
                 let logs_guard = logs.lock().unwrap();
-                let logs_text = logs_guard.iter().rev().take(20).cloned().collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n");
-                let full_text = format!("Scanning for new artifacts\n\nPress any key to close\n\n{}", logs_text);
-                let para = Paragraph::new(full_text).block(
-                    Block::default()
-                        .title("🔍 Scanning for new artifacts")
-                        .borders(Borders::ALL)
-                        .padding(Padding::new(1, 1, 1, 0))
-                        .style(Style::default().bg(Color::Rgb(0, 100, 100)).fg(Color::White)),
+                let header = "Scanning for new artifacts\n\n↑↓ PgUp/PgDn: Scroll | Any other key: Close\n\n";
+                // Inner size minus the 4 header rows
+                let window = visible_log_window(
+                    &logs_guard,
+                    popup_area.height.saturating_sub(7) as usize,
+                    popup_area.width.saturating_sub(4) as usize,
+                    *scroll,
                 );
+                let full_text = format!("{}{}", header, window.lines.join("\n"));
+                let para = Paragraph::new(full_text)
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::default()
+                            .title(format!("🔍 Scanning for new artifacts{}", window.indicator()))
+                            .borders(Borders::ALL)
+                            .padding(Padding::new(1, 1, 1, 0))
+                            .style(Style::default().bg(Color::Rgb(0, 100, 100)).fg(Color::White)),
+                    );
                 f.render_widget(para, popup_area);
             }
             PopupState::ArtifactActions { selected } => {
                 let popup_area = centered_rect(60, 30, area);
                 f.render_widget(Clear, popup_area);
-                let options = ["Delete", "Rebuild"];
                 let mut items = Vec::new();
-                for (i, &opt) in options.iter().enumerate() {
+                for (i, &opt) in ARTIFACT_ACTIONS.iter().enumerate() {
                     let style = if i == *selected {
                         Style::default().fg(Color::Black).bg(Color::Red).add_modifier(Modifier::BOLD)
                     } else {
@@ -170,11 +305,17 @@ impl PopupState {
                     .style(Style::default().bg(Color::Red));
                 f.render_widget(list, popup_area);
             }
-            PopupState::ClearAllConfirmation => {
-                let popup_area = centered_rect(70, 35, area);
+            PopupState::ClearAllConfirmation { entries, scroll, typed } => {
+                let popup_area = centered_rect(70, 60, area);
                 f.render_widget(Clear, popup_area);
-                let text = "⚠️  CLEAR ALL BUILDS - PERMANENT DELETION\n\nThis will delete ALL artifacts from the filesystem.\nThis action cannot be undone.\n\nAre you absolutely sure? (y: Confirm, n: Cancel)";
+                let text = format!(
+                    "⚠️  CLEAR ALL BUILDS - PERMANENT DELETION\n\nThis action cannot be undone. Protected (🔒) artifacts and ones hidden by a filter are kept.\nType {} and press Enter to confirm (Esc: Cancel, ↑↓: Scroll)\n\n> {}_\n\n{}",
+                    CLEAR_ALL_CONFIRM_WORD,
+                    typed,
+                    clear_all_summary(entries, CLEAR_ALL_LIST_LIMIT).join("\n")
+                );
                 let para = Paragraph::new(text)
+                    .scroll((*scroll, 0))
                     .block(Block::default().title("🔴 CLEAR ALL BUILDS").borders(Borders::ALL).style(Style::default().fg(Color::Black).bg(Color::Red)).padding(Padding::new(2, 2, 1, 1)))
                     .style(Style::default().fg(Color::Black).bg(Color::Red));
                 f.render_widget(para, popup_area);
@@ -203,6 +344,17 @@ impl PopupState {
                     .block(Block::default().title("Info").borders(Borders::ALL));
                 f.render_widget(para, popup_area);
             }
+            PopupState::Error { message } => {
+                let popup_area = centered_rect(50, 10, area);
+                f.render_widget(Clear, popup_area);
+                let para = Paragraph::new(format!("❌ {}", message)).wrap(Wrap { trim: false }).block(
+                    Block::default()
+                        .title("Error")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+                );
+                f.render_widget(para, popup_area);
+            }
             PopupState::ExcludedPathsList { paths, selected } => {
                 let popup_area = centered_rect(60, 40, area);
                 f.render_widget(Clear, popup_area);
@@ -216,13 +368,197 @@ impl PopupState {
                         } else {
                             Style::default()
                         };
-                        items.push(ListItem::new(Span::styled(path.as_str(), style)));
+                        let width = popup_area.width.saturating_sub(2) as usize;
+                        items.push(ListItem::new(Span::styled(truncate_middle(path, width), style)));
                     }
                 }
                 let list = List::new(items)
                     .block(Block::default().title("Excluded Paths (↑↓ Enter to remove Esc)").borders(Borders::ALL));
                 f.render_widget(list, popup_area);
             }
+            PopupState::ScanDiff { lines, selected } => {
+                let popup_area = centered_rect(70, 50, area);
+                f.render_widget(Clear, popup_area);
+                let items: Vec<ListItem> = if lines.is_empty() {
+                    vec![ListItem::new("No changes between the last two scans")]
+                } else {
+                    lines
+                        .iter()
+                        .map(|line| {
+                            let color = if line.starts_with('+') {
+                                Color::Green
+                            } else if line.starts_with('↑') {
+                                Color::Red
+                            } else {
+                                Color::Cyan
+                            };
+                            ListItem::new(Span::styled(line.as_str(), Style::default().fg(color)))
+                        })
+                        .collect()
+                };
+                let list = List::new(items)
+                    .block(Block::default().title("🔀 Scan Diff (↑↓ Esc)").borders(Borders::ALL))
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+                let mut state = ListState::default();
+                state.select(Some(*selected));
+                f.render_stateful_widget(list, popup_area, &mut state);
+            }
+            PopupState::Duplicates { lines, selected } => {
+                let popup_area = centered_rect(70, 50, area);
+                f.render_widget(Clear, popup_area);
+                let width = popup_area.width.saturating_sub(2) as usize;
+                let items: Vec<ListItem> = if lines.is_empty() {
+                    vec![ListItem::new("No duplicate node_modules trees found (projects need a lockfile)")]
+                } else {
+                    lines
+                        .iter()
+                        .map(|line| match line.strip_prefix("  ") {
+                            Some(path) => ListItem::new(format!("  {}", truncate_middle(path, width.saturating_sub(2)))),
+                            None => ListItem::new(Span::styled(line.as_str(), Style::default().fg(Color::Yellow))),
+                        })
+                        .collect()
+                };
+                let list = List::new(items)
+                    .block(Block::default().title("♊ Duplicate Dependency Trees (↑↓ Esc)").borders(Borders::ALL))
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+                let mut state = ListState::default();
+                state.select(Some(*selected));
+                f.render_stateful_widget(list, popup_area, &mut state);
+            }
+            PopupState::LanguageUsage { lines } => {
+                let popup_area = centered_rect(60, 50, area);
+                f.render_widget(Clear, popup_area);
+                let items: Vec<ListItem> = if lines.is_empty() {
+                    vec![ListItem::new("No artifacts recorded yet - run a scan first")]
+                } else {
+                    lines.iter().map(|line| ListItem::new(line.as_str())).collect()
+                };
+                let list = List::new(items)
+                    .block(Block::default().title("📦 Disk Usage by Language (any key to close)").borders(Borders::ALL));
+                f.render_widget(list, popup_area);
+            }
+            PopupState::Subdirectories { parent, entries, selected, loading } => {
+                let popup_area = centered_rect(70, 50, area);
+                f.render_widget(Clear, popup_area);
+                let items: Vec<ListItem> = if *loading {
+                    vec![ListItem::new("Sizing subdirectories...")]
+                } else if entries.is_empty() {
+                    vec![ListItem::new("No subdirectories")]
+                } else {
+                    entries
+                        .iter()
+                        .map(|(path, size)| {
+                            let name = std::path::Path::new(path)
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_else(|| path.clone());
+                            ListItem::new(format!("{:>8}  {}", format_bytes(*size), name))
+                        })
+                        .collect()
+                };
+                let list = List::new(items)
+                    .block(Block::default().title(format!("📂 {} (↑↓ Nav, Enter/d: Delete, Esc)", parent)).borders(Borders::ALL))
+                    .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+                let mut state = ListState::default();
+                state.select(Some(*selected));
+                f.render_stateful_widget(list, popup_area, &mut state);
+            }
+            PopupState::SizeTree { nodes, selected, sizing } => {
+                let popup_area = centered_rect(70, 60, area);
+                f.render_widget(Clear, popup_area);
+                let width = popup_area.width.saturating_sub(2) as usize;
+                let items: Vec<ListItem> = nodes
+                    .iter()
+                    .map(|node| {
+                        let indent = "  ".repeat(node.depth);
+                        let marker = if node.expanded { "▾" } else { "▸" };
+                        let prefix = format!("{}{} {:>8}  ", indent, marker, format_bytes(node.size));
+                        let name = if node.depth == 0 {
+                            truncate_middle(&node.path, width.saturating_sub(prefix.chars().count()))
+                        } else {
+                            std::path::Path::new(&node.path)
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_else(|| node.path.clone())
+                        };
+                        let mut line = vec![Span::raw(prefix), Span::raw(name)];
+                        if node.omitted > 0 {
+                            line.push(Span::styled(format!("  (+{} more not shown)", node.omitted), Style::default().fg(Color::DarkGray)));
+                        }
+                        if sizing.as_deref() == Some(node.path.as_str()) {
+                            line.push(Span::styled("  (sizing...)", Style::default().fg(Color::DarkGray)));
+                        }
+                        ListItem::new(Line::from(line))
+                    })
+                    .collect();
+                let list = List::new(items)
+                    .block(Block::default().title("🌳 Size tree (↑↓ Nav, Enter/→: Expand, ←: Collapse, Esc)").borders(Borders::ALL))
+                    .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
+                let mut state = ListState::default();
+                state.select(Some(*selected));
+                f.render_stateful_widget(list, popup_area, &mut state);
+            }
+            PopupState::LargestFiles { parent, files, truncated, loading } => {
+                let popup_area = centered_rect(70, 50, area);
+                f.render_widget(Clear, popup_area);
+                let mut items: Vec<ListItem> = if *loading {
+                    vec![ListItem::new("Looking for the largest files...")]
+                } else if files.is_empty() {
+                    vec![ListItem::new("No files")]
+                } else {
+                    files
+                        .iter()
+                        .map(|(path, size)| ListItem::new(format!("{:>8}  {}", format_bytes(*size), path)))
+                        .collect()
+                };
+                if *truncated {
+                    items.push(ListItem::new(Span::styled(
+                        "Stopped early in a very large tree - results may be incomplete",
+                        Style::default().fg(Color::Yellow),
+                    )));
+                }
+                let list = List::new(items)
+                    .block(Block::default().title(format!("📄 Largest files in {} (any key to close)", parent)).borders(Borders::ALL));
+                f.render_widget(list, popup_area);
+            }
+            PopupState::DeletionFailures { failures, selected, retry_action } => {
+                let popup_area = centered_rect(80, 50, area);
+                f.render_widget(Clear, popup_area);
+                let items: Vec<ListItem> = failures
+                    .iter()
+                    .map(|(path, reason)| {
+                        ListItem::new(vec![
+                            Line::from(path.as_str()),
+                            Line::from(Span::styled(format!("  {}", reason), Style::default().fg(Color::Red))),
+                        ])
+                    })
+                    .collect();
+                let keys = if retry_action.is_some() { "↑↓ Scroll, Enter: Retry, Esc: Close" } else { "↑↓ Scroll, Esc: Close" };
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .title(format!("❌ {} deletion(s) failed ({})", failures.len(), keys))
+                            .borders(Borders::ALL)
+                            .border_style(Style::default().fg(Color::Red)),
+                    )
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+                let mut state = ListState::default();
+                state.select(Some(*selected));
+                f.render_stateful_widget(list, popup_area, &mut state);
+            }
+            PopupState::Help { text } => {
+                let popup_area = centered_rect(60, 70, area);
+                f.render_widget(Clear, popup_area);
+                let para = Paragraph::new(text.as_str())
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::default()
+                            .title("❓ Keys (any key to close)")
+                            .borders(Borders::ALL)
+                            .padding(Padding::new(1, 1, 1, 0)),
+                    );
+                f.render_widget(para, popup_area);
+            }
             PopupState::None => {}
         }
     }
@@ -234,11 +570,11 @@ impl PopupState {
                     if *selected > 0 {
                         *selected -= 1;
                     } else {
-                        *selected = 3; // Wrap to last
+                        *selected = SETTINGS_OPTIONS.len() - 1; // Wrap to last
                     }
                 }
                 KeyCode::Down => {
-                    if *selected < 3 {
+                    if *selected < SETTINGS_OPTIONS.len() - 1 {
                         *selected += 1;
                     } else {
                         *selected = 0; // Wrap to first
@@ -250,6 +586,8 @@ impl PopupState {
                         1 => Some(PopupCommand::OpenDirBrowse),
                         2 => Some(PopupCommand::ToggleRemoval),
                         3 => Some(PopupCommand::OpenExcludedPaths),
+                        4 => Some(PopupCommand::ToggleDocker),
+                        5 => Some(PopupCommand::ToggleVimKeys),
                         _ => None,
                     };
                     if cmd.is_some() {
@@ -280,14 +618,17 @@ impl PopupState {
                 }
                 _ => {}
             },
-            PopupState::Logs { .. } => match key.code {
-                KeyCode::Esc => {
+            PopupState::Logs { logs, scroll } => {
+                let total = logs.lock().unwrap().len();
+                if !scroll_logs(scroll, total, key.code) && key.code == KeyCode::Esc {
+                    *self = PopupState::None;
+                }
+            }
+            PopupState::Scanning { logs, scroll } => {
+                let total = logs.lock().unwrap().len();
+                if !scroll_logs(scroll, total, key.code) {
                     *self = PopupState::None;
                 }
-                _ => {}
-            },
-            PopupState::Scanning { .. } => {
-                *self = PopupState::None;
                 return None;
             }
             PopupState::ArtifactActions { selected } => match key.code {
@@ -295,11 +636,11 @@ impl PopupState {
                     if *selected > 0 {
                         *selected -= 1;
                     } else {
-                        *selected = 1; // Wrap to last
+                        *selected = ARTIFACT_ACTIONS.len() - 1; // Wrap to last
                     }
                 }
                 KeyCode::Down => {
-                    if *selected < 1 {
+                    if *selected < ARTIFACT_ACTIONS.len() - 1 {
                         *selected += 1;
                     } else {
                         *selected = 0; // Wrap to first
@@ -309,6 +650,9 @@ impl PopupState {
                     let cmd = match *selected {
                         0 => Some(PopupCommand::DeleteArtifact),
                         1 => Some(PopupCommand::RebuildArtifact),
+                        2 => Some(PopupCommand::BrowseSubdirectories),
+                        3 => Some(PopupCommand::ShowLargestFiles),
+                        4 => Some(PopupCommand::ShowSizeTree),
                         _ => None,
                     };
                     if cmd.is_some() {
@@ -321,12 +665,25 @@ impl PopupState {
                 }
                 _ => {}
             },
-            PopupState::ClearAllConfirmation => match key.code {
-                KeyCode::Char('y') | KeyCode::Char('Y') => {
+            PopupState::ClearAllConfirmation { scroll, typed, .. } => match key.code {
+                KeyCode::Up => {
+                    *scroll = scroll.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    *scroll = scroll.saturating_add(1);
+                }
+                KeyCode::Char(c) => {
+                    typed.push(c);
+                }
+                KeyCode::Backspace => {
+                    typed.pop();
+                }
+                // A single stray key must never wipe everything, so the word has to be typed out
+                KeyCode::Enter if typed.as_str() == CLEAR_ALL_CONFIRM_WORD => {
                     *self = PopupState::None;
                     return Some(PopupCommand::ClearAllBuilds);
                 }
-                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                KeyCode::Esc => {
                     *self = PopupState::None;
                 }
                 _ => {}
@@ -344,69 +701,73 @@ impl PopupState {
                     _ => {}
                 }
             }
-            PopupState::Progress { .. } => match key.code {
-                KeyCode::Esc => {
+            PopupState::Progress { .. } => {
+                if key.code == KeyCode::Esc {
                     *self = PopupState::None;
                 }
-                _ => {}
-            },
-            PopupState::Info { .. } => {
+            }
+            PopupState::Info { .. } | PopupState::Error { .. } | PopupState::Help { .. } | PopupState::LanguageUsage { .. } | PopupState::LargestFiles { .. } => {
                 *self = PopupState::None;
             },
-            PopupState::DirBrowse { path, items, selected } => match key.code {
-                KeyCode::Up => {
-                    if *selected > 0 {
-                        *selected -= 1;
-                    }
+            PopupState::DirBrowse { path, all_items, items, filter, selected } => match key.code {
+                KeyCode::Up if *selected > 0 => {
+                    *selected -= 1;
                 }
-                KeyCode::Down => {
-                    if *selected < items.len().saturating_sub(1) {
-                        *selected += 1;
-                    }
+                KeyCode::Down if *selected < items.len().saturating_sub(1) => {
+                    *selected += 1;
                 }
-                KeyCode::Enter => {
-                    if *selected < items.len() {
-                        let item = &items[*selected];
-                        if item == ".." {
-                            // Go up
-                            if let Some(parent) = std::path::Path::new(path).parent() {
-                                *path = parent.display().to_string();
-                                *items = get_dir_items(path);
-                                *selected = 0;
-                            }
-                        } else {
-                            // Enter dir
-                            let new_path = std::path::Path::new(path).join(item);
-                            if new_path.is_dir() {
-                                *path = new_path.display().to_string();
-                                *items = get_dir_items(path);
-                                *selected = 0;
-                            }
-                        }
+                KeyCode::Enter if *selected < items.len() => {
+                    let item = &items[*selected];
+                    let new_path = if item == ".." {
+                        // Go up
+                        std::path::Path::new(path).parent().map(|p| p.to_path_buf())
+                    } else {
+                        // Enter dir
+                        Some(std::path::Path::new(path).join(item)).filter(|p| p.is_dir())
+                    };
+                    if let Some(new_path) = new_path {
+                        *path = new_path.display().to_string();
+                        *all_items = get_dir_items(path);
+                        *items = all_items.clone();
+                        filter.clear();
+                        *selected = 0;
                     }
                 }
-                KeyCode::Char('s') => {
-                    if *selected < items.len() {
-                        let item = &items[*selected];
-                        let selected_path = if item == ".." {
-                            if let Some(parent) = std::path::Path::new(path).parent() {
-                                parent.display().to_string()
-                            } else {
-                                path.clone()
-                            }
+                KeyCode::Tab if *selected < items.len() => {
+                    let item = &items[*selected];
+                    let selected_path = if item == ".." {
+                        if let Some(parent) = std::path::Path::new(path).parent() {
+                            parent.display().to_string()
                         } else {
-                            std::path::Path::new(path).join(item).display().to_string()
-                        };
-                        *self = PopupState::None;
-                        return Some(PopupCommand::SetValue { key: "Scan Path".to_string(), value: selected_path });
-                    }
+                            path.clone()
+                        }
+                    } else {
+                        std::path::Path::new(path).join(item).display().to_string()
+                    };
+                    *self = PopupState::None;
+                    return Some(PopupCommand::SetValue { key: "Scan Path".to_string(), value: selected_path });
                 }
-                KeyCode::Char(' ') => {
+                KeyCode::Char(' ') if filter.is_empty() => {
                     // Select current directory
                     let current_path = path.clone();
                     *self = PopupState::None;
                     return Some(PopupCommand::SetValue { key: "Scan Path".to_string(), value: current_path });
                 }
+                KeyCode::Char(c) => {
+                    filter.push(c);
+                    *items = filter_dir_items(all_items, filter);
+                    *selected = 0;
+                }
+                KeyCode::Backspace if !filter.is_empty() => {
+                    filter.pop();
+                    *items = filter_dir_items(all_items, filter);
+                    *selected = 0;
+                }
+                KeyCode::Esc if !filter.is_empty() => {
+                    filter.clear();
+                    *items = all_items.clone();
+                    *selected = 0;
+                }
                 KeyCode::Esc => {
                     *self = PopupState::None;
                 }
@@ -429,12 +790,74 @@ impl PopupState {
                         *selected = 0; // Wrap to first
                     }
                 }
-                KeyCode::Enter => {
-                    if !paths.is_empty() {
-                        let path = paths[*selected].clone();
-                        let message = format!("Remove '{}' from exclusion list?", path);
-                        *self = PopupState::new_confirm_action(message, format!("remove_excluded:{}", path));
-                        return None;
+                KeyCode::Enter if !paths.is_empty() => {
+                    let path = paths[*selected].clone();
+                    let message = format!("Remove '{}' from exclusion list?", path);
+                    *self = PopupState::new_confirm_action(message, format!("remove_excluded:{}", path));
+                    return None;
+                }
+                KeyCode::Esc => {
+                    *self = PopupState::None;
+                }
+                _ => {}
+            },
+            PopupState::DeletionFailures { failures, selected, retry_action } => match key.code {
+                KeyCode::Up if *selected > 0 => {
+                    *selected -= 1;
+                }
+                KeyCode::Down if *selected < failures.len().saturating_sub(1) => {
+                    *selected += 1;
+                }
+                KeyCode::Enter if retry_action.is_some() => {
+                    let action = retry_action.take().unwrap_or_default();
+                    *self = PopupState::None;
+                    return Some(PopupCommand::ConfirmAction { action });
+                }
+                KeyCode::Esc | KeyCode::Enter => {
+                    *self = PopupState::None;
+                }
+                _ => {}
+            },
+            PopupState::Subdirectories { entries, selected, .. } => match key.code {
+                KeyCode::Up if *selected > 0 => {
+                    *selected -= 1;
+                }
+                KeyCode::Down if *selected < entries.len().saturating_sub(1) => {
+                    *selected += 1;
+                }
+                KeyCode::Enter | KeyCode::Char('d') if *selected < entries.len() => {
+                    let (path, size) = entries[*selected].clone();
+                    let message = format!("Delete {} ({})?\n\nThe rest of the artifact is kept.", path, format_bytes(size));
+                    *self = PopupState::new_confirm_action(message, format!("delete_subdir:{}", path));
+                }
+                KeyCode::Esc => {
+                    *self = PopupState::None;
+                }
+                _ => {}
+            },
+            PopupState::SizeTree { nodes, selected, sizing } => match key.code {
+                KeyCode::Up if *selected > 0 => {
+                    *selected -= 1;
+                }
+                KeyCode::Down if *selected < nodes.len().saturating_sub(1) => {
+                    *selected += 1;
+                }
+                KeyCode::Enter | KeyCode::Right
+                    if sizing.is_none() && *selected < nodes.len() && !nodes[*selected].expanded && nodes[*selected].depth < SIZE_TREE_MAX_DEPTH =>
+                {
+                    // Sized on demand, one level at a time, off the UI thread
+                    let path = nodes[*selected].path.clone();
+                    *sizing = Some(path.clone());
+                    return Some(PopupCommand::ExpandSizeNode { path });
+                }
+                KeyCode::Enter | KeyCode::Left if *selected < nodes.len() && nodes[*selected].expanded => {
+                    collapse_size_node(nodes, *selected);
+                }
+                KeyCode::Left if *selected < nodes.len() => {
+                    // On a collapsed row, jump to its parent
+                    let depth = nodes[*selected].depth;
+                    if let Some(parent) = nodes[..*selected].iter().rposition(|n| n.depth < depth) {
+                        *selected = parent;
                     }
                 }
                 KeyCode::Esc => {
@@ -442,12 +865,93 @@ impl PopupState {
                 }
                 _ => {}
             },
+            PopupState::ScanDiff { lines, selected } | PopupState::Duplicates { lines, selected } => match key.code {
+                KeyCode::Up if *selected > 0 => {
+                    *selected -= 1;
+                }
+                KeyCode::Down if *selected < lines.len().saturating_sub(1) => {
+                    *selected += 1;
+                }
+                KeyCode::Esc => {
+                    *self = PopupState::None;
+                }
+                _ => {}
+            },
             PopupState::None => {}
         }
         None
     }
 }
 
+const ARTIFACT_ACTIONS: [&str; 5] = ["Delete", "Rebuild", "Browse Subdirectories", "Largest Files", "Size Tree"];
+const CLEAR_ALL_LIST_LIMIT: usize = 50;
+const CLEAR_ALL_CONFIRM_WORD: &str = "DELETE";
+
+pub fn clear_all_summary(entries: &[(String, u64)], limit: usize) -> Vec<String> {
+    let total: u64 = entries.iter().map(|(_, size)| size).sum();
+    let mut lines = vec![format!(
+        "{} directories, {} will be deleted:",
+        entries.len(),
+        format_bytes(total)
+    )];
+    for (path, size) in entries.iter().take(limit) {
+        lines.push(format!("  {:>9}  {}", format_bytes(*size), path));
+    }
+    if entries.len() > limit {
+        lines.push(format!("  ...and {} more", entries.len() - limit));
+    }
+    lines
+}
+
+pub struct LogWindow {
+    pub lines: Vec<String>,
+    pub hidden_above: usize,
+    pub hidden_below: usize,
+}
+
+impl LogWindow {
+    fn indicator(&self) -> String {
+        if self.hidden_above == 0 && self.hidden_below == 0 {
+            String::new()
+        } else {
+            format!(" [{} above, {} below]", self.hidden_above, self.hidden_below)
+        }
+    }
+}
+
+pub fn visible_log_window(logs: &[String], height: usize, width: usize, scroll: usize) -> LogWindow {
+    // `scroll` counts lines up from the newest entry; 0 follows the tail
+    let end = logs.len().saturating_sub(scroll);
+    let width = width.max(1);
+    let mut rows = 0;
+    let mut start = end;
+    while start > 0 {
+        let wrapped = logs[start - 1].chars().count().div_ceil(width).max(1);
+        if rows + wrapped > height.max(1) && start < end {
+            break;
+        }
+        rows += wrapped;
+        start -= 1;
+    }
+    LogWindow {
+        lines: logs[start..end].to_vec(),
+        hidden_above: start,
+        hidden_below: logs.len() - end,
+    }
+}
+
+fn scroll_logs(scroll: &mut usize, total: usize, code: KeyCode) -> bool {
+    let max_scroll = total.saturating_sub(1);
+    match code {
+        KeyCode::Up => *scroll = (*scroll + 1).min(max_scroll),
+        KeyCode::Down => *scroll = scroll.saturating_sub(1),
+        KeyCode::PageUp => *scroll = (*scroll + 10).min(max_scroll),
+        KeyCode::PageDown => *scroll = scroll.saturating_sub(10),
+        _ => return false,
+    }
+    true
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -468,14 +972,27 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
+pub fn filter_dir_items(items: &[String], filter: &str) -> Vec<String> {
+    // Case-insensitive substring match; ".." only makes sense while unfiltered
+    if filter.is_empty() {
+        return items.to_vec();
+    }
+    let needle = filter.to_lowercase();
+    items
+        .iter()
+        .filter(|item| item.as_str() != ".." && item.to_lowercase().contains(&needle))
+        .cloned()
+        .collect()
+}
+
 fn get_dir_items(path: &str) -> Vec<String> {
     let mut items = vec!["..".to_string()];
     if let Ok(entries) = std::fs::read_dir(path) {
         for entry in entries.flatten() {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_dir() {
-                    items.push(entry.file_name().to_string_lossy().to_string());
-                }
+            if let Ok(file_type) = entry.file_type()
+                && file_type.is_dir()
+            {
+                items.push(entry.file_name().to_string_lossy().to_string());
             }
         }
     }
@@ -0,0 +1,72 @@
+// Background work started from the UI, and how it ends when the user quits
+
+use crate::utils::logger::log_to_file;
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskKind {
+    /// Deletes files or rows; quitting waits for it so nothing is left half-removed.
+    Critical,
+    /// Safe to stop at any point; the next scan redoes the work.
+    Cancellable,
+}
+
+struct TrackedTask {
+    label: String,
+    kind: TaskKind,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct BackgroundTasks {
+    tasks: Vec<TrackedTask>,
+}
+
+impl BackgroundTasks {
+    pub fn spawn<F>(&mut self, label: impl Into<String>, kind: TaskKind, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.retain(|t| !t.handle.is_finished());
+        self.tasks.push(TrackedTask { label: label.into(), kind, handle: tokio::spawn(task) });
+    }
+
+    /// Tasks still running, for the title bar spinner.
+    pub fn active(&self) -> usize {
+        self.tasks.iter().filter(|t| !t.handle.is_finished()).count()
+    }
+
+    /// Labels of the unfinished tasks of one kind.
+    pub fn pending(&self, kind: TaskKind) -> Vec<String> {
+        self.tasks.iter().filter(|t| t.kind == kind && !t.handle.is_finished()).map(|t| t.label.clone()).collect()
+    }
+
+    /// Aborts cancellable tasks and gives critical ones up to `grace` to finish.
+    ///
+    /// Returns the critical tasks still running when the grace period ran out. Work already
+    /// handed to a blocking thread (an `rm` in progress) can't be interrupted either way.
+    pub async fn shutdown(&mut self, grace: Duration) -> Vec<String> {
+        let mut critical = vec![];
+        for task in self.tasks.drain(..).filter(|t| !t.handle.is_finished()) {
+            match task.kind {
+                TaskKind::Cancellable => {
+                    task.handle.abort();
+                    log_to_file(&format!("Quit: interrupted {}", task.label));
+                }
+                TaskKind::Critical => critical.push(task),
+            }
+        }
+        let deadline = tokio::time::Instant::now() + grace;
+        let mut cut_off = vec![];
+        for mut task in critical {
+            if tokio::time::timeout_at(deadline, &mut task.handle).await.is_err() {
+                log_to_file(&format!("Quit: {} did not finish within {}s and was stopped", task.label, grace.as_secs()));
+                task.handle.abort();
+                cut_off.push(task.label);
+            }
+        }
+        cut_off
+    }
+}
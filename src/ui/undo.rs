@@ -0,0 +1,51 @@
+// Bounded undo/redo history
+
+use std::collections::VecDeque;
+
+/// Undo and redo stacks of labelled snapshots, each holding at most `limit` entries.
+///
+/// Callers record the state from *before* a change; undoing hands back that state and keeps
+/// the current one so the change can be redone.
+pub struct UndoHistory<T> {
+    undo: VecDeque<(String, T)>,
+    redo: Vec<(String, T)>,
+    limit: usize,
+}
+
+impl<T> UndoHistory<T> {
+    pub fn new(limit: usize) -> Self {
+        UndoHistory { undo: VecDeque::new(), redo: vec![], limit }
+    }
+
+    /// Records a new change; anything that was undone can no longer be redone.
+    pub fn record(&mut self, label: impl Into<String>, before: T) {
+        self.redo.clear();
+        if self.limit == 0 {
+            return;
+        }
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back((label.into(), before));
+    }
+
+    /// Returns the label and state to restore, stashing `current` for redo.
+    pub fn undo(&mut self, current: T) -> Option<(String, T)> {
+        let (label, before) = self.undo.pop_back()?;
+        self.redo.push((label.clone(), current));
+        if self.redo.len() > self.limit {
+            self.redo.remove(0);
+        }
+        Some((label, before))
+    }
+
+    /// Returns the label and state to reapply, stashing `current` for undo.
+    pub fn redo(&mut self, current: T) -> Option<(String, T)> {
+        let (label, after) = self.redo.pop()?;
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back((label.clone(), current));
+        Some((label, after))
+    }
+}
@@ -0,0 +1,234 @@
+// Cross-platform artifact deletion
+
+use crate::utils::logger::log_to_file;
+use crate::utils::{path_from_string, raw_byte};
+use std::io;
+use std::time::Duration;
+
+/// Privilege tools tried for root-owned artifacts, in order of preference.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escalation {
+    Sudo,
+    Doas,
+    Pkexec,
+}
+
+#[cfg(unix)]
+impl Escalation {
+    const ALL: [Escalation; 3] = [Escalation::Sudo, Escalation::Doas, Escalation::Pkexec];
+
+    pub fn program(self) -> &'static str {
+        match self {
+            Escalation::Sudo => "sudo",
+            Escalation::Doas => "doas",
+            Escalation::Pkexec => "pkexec",
+        }
+    }
+}
+
+/// First privilege tool installed in one of the `PATH` directories.
+#[cfg(unix)]
+pub fn escalation_from_path(path_var: &std::ffi::OsStr) -> Option<Escalation> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let installed = |program: &str| {
+        std::env::split_paths(path_var).any(|dir| {
+            std::fs::metadata(dir.join(program)).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        })
+    };
+    Escalation::ALL.into_iter().find(|tool| installed(tool.program()))
+}
+
+#[cfg(unix)]
+fn escalation() -> Option<Escalation> {
+    static TOOL: std::sync::OnceLock<Option<Escalation>> = std::sync::OnceLock::new();
+    *TOOL.get_or_init(|| escalation_from_path(&std::env::var_os("PATH").unwrap_or_default()))
+}
+
+/// Whether a failed deletion can be retried with a password prompt; only sudo reads one from stdin.
+#[cfg(unix)]
+pub fn can_prompt_for_password() -> bool {
+    escalation() == Some(Escalation::Sudo)
+}
+
+#[cfg(not(unix))]
+pub fn can_prompt_for_password() -> bool {
+    false
+}
+
+const REMOVE_ATTEMPTS: u32 = 3;
+const REMOVE_BACKOFF: Duration = Duration::from_millis(200);
+
+pub fn is_transient_removal_error(error: &io::Error) -> bool {
+    match error.kind() {
+        // Something is still writing into the tree or holds it open
+        io::ErrorKind::ResourceBusy | io::ErrorKind::DirectoryNotEmpty => true,
+        // Windows reports files locked by editors, watchers and antivirus as access denied
+        io::ErrorKind::PermissionDenied => cfg!(windows),
+        _ => false,
+    }
+}
+
+pub fn remove_with_retry(path: &str) -> io::Result<()> {
+    let mut attempt = 1;
+    loop {
+        match std::fs::remove_dir_all(path_from_string(path)) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) if attempt < REMOVE_ATTEMPTS && is_transient_removal_error(&e) => {
+                log_to_file(&format!("Retrying deletion of {} (attempt {}/{}): {}", path, attempt + 1, REMOVE_ATTEMPTS, e));
+                std::thread::sleep(REMOVE_BACKOFF * attempt);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn delete_path(path: &str, password: Option<&str>) -> io::Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    // Artifacts we own go without sudo; root-owned leftovers (e.g. from Docker builds) need escalation
+    let direct_error = match password {
+        None => match remove_with_retry(path) {
+            Ok(()) => return Ok(()),
+            Err(e) => Some(e),
+        },
+        Some(_) => None,
+    };
+
+    let Some(tool) = escalation() else {
+        let cause = direct_error.map(|e| format!(" ({})", describe_delete_error(&e))).unwrap_or_default();
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("couldn't get root privileges: none of sudo, doas or pkexec is installed{}", cause),
+        ));
+    };
+    let mut cmd = Command::new(tool.program());
+    match tool {
+        // sudo -n fails fast when a password is needed; the caller then prompts and retries with -S
+        Escalation::Sudo => {
+            cmd.arg(if password.is_some() { "-S" } else { "-n" });
+        }
+        // doas can't take a password on stdin, so only rules with nopass work from the TUI
+        Escalation::Doas => {
+            cmd.arg("-n");
+        }
+        // pkexec asks through the desktop's polkit agent
+        Escalation::Pkexec => {}
+    }
+    cmd.arg("rm").arg("-rf").arg(path_from_string(path));
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+
+    let status = match password.filter(|_| tool == Escalation::Sudo) {
+        Some(pwd) => cmd.stdin(Stdio::piped()).spawn().and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(format!("{}\n", pwd).as_bytes());
+            }
+            child.wait()
+        }),
+        None => cmd.stdin(Stdio::null()).status(),
+    };
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        // The direct attempt says more about the cause (busy, read-only, ...) than sudo's exit code
+        _ if direct_error.is_some() => Err(direct_error.unwrap()),
+        Ok(status) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            if password.is_some() {
+                format!("{} rm exited with {} (wrong password?)", tool.program(), status)
+            } else {
+                format!("{} rm exited with {}", tool.program(), status)
+            },
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn delete_path(path: &str, _password: Option<&str>) -> io::Result<()> {
+    remove_with_retry(path)
+}
+
+pub fn describe_delete_error(error: &io::Error) -> String {
+    // Errors we built ourselves (privilege tools, hooks) already carry a readable message
+    if error.get_ref().is_some() {
+        return error.to_string();
+    }
+    match error.kind() {
+        io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+        io::ErrorKind::NotFound => "not found".to_string(),
+        io::ErrorKind::ResourceBusy => "busy (in use by another process)".to_string(),
+        io::ErrorKind::DirectoryNotEmpty => "still being written to".to_string(),
+        io::ErrorKind::ReadOnlyFilesystem => "read-only filesystem".to_string(),
+        _ => error.to_string(),
+    }
+}
+
+pub fn shell_quote(path: &str) -> String {
+    // Single quotes keep $, backticks and spaces literal; embedded quotes are closed and reopened.
+    // Bytes that aren't UTF-8 (stored escaped, see path_to_string) come from printf, so the
+    // script stays plain text and still names the real directory.
+    let mut quoted = String::from("'");
+    for c in path.chars() {
+        match raw_byte(c) {
+            Some(byte) => quoted.push_str(&format!("'\"$(printf '\\{:03o}')\"'", byte)),
+            None if c == '\'' => quoted.push_str("'\\''"),
+            None => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+pub fn cleanup_script(paths: &[String], generated_at: &str) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n# Generated by ratifact on {}\n# Review before running: every line deletes a directory tree.\nset -e\n\n",
+        generated_at
+    );
+    for path in paths {
+        script.push_str(&format!("rm -rf {}\n", shell_quote(path)));
+    }
+    script
+}
+
+pub fn write_cleanup_script(path: &std::path::Path, paths: &[String]) -> io::Result<()> {
+    let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    std::fs::write(path, cleanup_script(paths, &generated_at))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+pub fn run_pre_delete_hook(hook: &str, path: &str) -> Result<(), String> {
+    use std::process::{Command, Stdio};
+
+    // The path is passed as a separate argument ("$1") so spaces and quotes in it stay intact
+    #[cfg(unix)]
+    let output = Command::new("sh").arg("-c").arg(format!("{} \"$1\"", hook)).arg("ratifact").arg(path_from_string(path))
+        .stdin(Stdio::null())
+        .output();
+    #[cfg(not(unix))]
+    let output = Command::new("cmd").arg("/C").arg(hook).arg(path_from_string(path))
+        .stdin(Stdio::null())
+        .output();
+
+    let output = output.map_err(|e| format!("pre_delete_hook could not start: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(if stderr.is_empty() {
+            format!("pre_delete_hook exited with {}", output.status)
+        } else {
+            format!("pre_delete_hook exited with {}: {}", output.status, stderr)
+        })
+    }
+}
@@ -0,0 +1,15 @@
+// Desktop notifications
+
+use crate::utils::logger::log_to_file;
+
+/// Shows a desktop notification; a missing notification daemon is logged, never shown as an error.
+pub fn notify_desktop(summary: &str, body: &str) {
+    let result = notify_rust::Notification::new()
+        .appname("ratifact")
+        .summary(summary)
+        .body(body)
+        .show();
+    if let Err(e) = result {
+        log_to_file(&format!("Desktop notification failed: {}", e));
+    }
+}
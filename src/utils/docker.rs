@@ -0,0 +1,74 @@
+// Docker disk usage helpers
+
+use std::process::{Command, Stdio};
+
+pub const DOCKER_IMAGES_ARTIFACT: &str = "docker://dangling-images";
+pub const DOCKER_BUILD_CACHE_ARTIFACT: &str = "docker://build-cache";
+
+pub fn is_docker_artifact(path: &str) -> bool {
+    path.starts_with("docker://")
+}
+
+pub fn parse_docker_size(size: &str) -> u64 {
+    // Docker prints decimal units like "1.2GB", "512kB" or "0B", sometimes followed by " (50%)"
+    let value = size.split_whitespace().next().unwrap_or("");
+    let split_at = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split_at);
+    let number: f64 = match number.parse() {
+        Ok(n) => n,
+        Err(_) => return 0,
+    };
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        _ => return 0,
+    };
+    (number * multiplier) as u64
+}
+
+fn docker_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("docker")
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
+
+pub fn docker_disk_usage() -> Vec<(String, u64)> {
+    let mut usage = vec![];
+    // Dangling images are what `docker image prune` removes
+    if let Some(out) = docker_output(&["images", "--filter", "dangling=true", "--format", "{{.Size}}"]) {
+        let size: u64 = out.lines().map(parse_docker_size).sum();
+        usage.push((DOCKER_IMAGES_ARTIFACT.to_string(), size));
+    }
+    if let Some(out) = docker_output(&["system", "df", "--format", "{{.Type}}\t{{.Reclaimable}}"]) {
+        for line in out.lines() {
+            if let Some((kind, reclaimable)) = line.split_once('\t')
+                && kind == "Build Cache"
+            {
+                usage.push((DOCKER_BUILD_CACHE_ARTIFACT.to_string(), parse_docker_size(reclaimable)));
+            }
+        }
+    }
+    usage
+}
+
+pub fn prune_docker_artifact(path: &str) -> bool {
+    let args: &[&str] = match path {
+        DOCKER_IMAGES_ARTIFACT => &["image", "prune", "-f"],
+        DOCKER_BUILD_CACHE_ARTIFACT => &["builder", "prune", "-f"],
+        _ => return false,
+    };
+    docker_output(args).is_some()
+}
@@ -1,15 +1,19 @@
 // Utility helper functions
 
-use std::path::Path;
+use crate::tracking::scanner::ARTIFACT_DIRS;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use walkdir::WalkDir;
 
 pub fn detect_language_for_path(path: &str) -> String {
-    let p = Path::new(path);
+    let p = &path_from_string(path);
     if p.join("Cargo.toml").exists() {
         "Rust".to_string()
     } else if p.join("package.json").exists() {
         "JavaScript".to_string()
-    } else if p.join("pyproject.toml").exists() {
+    } else if p.join("pyproject.toml").exists() || p.join("requirements.txt").exists() || p.join("setup.py").exists() {
         "Python".to_string()
     } else if p.join("go.mod").exists() {
         "Go".to_string()
@@ -36,9 +40,216 @@ pub fn detect_language_for_path(path: &str) -> String {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct LanguageCache {
+    entries: HashMap<String, (Option<SystemTime>, String)>,
+}
+
+impl LanguageCache {
+    pub fn detect(&mut self, path: &str) -> String {
+        // Adding or removing a manifest bumps the project dir mtime, which invalidates the entry
+        let mtime = std::fs::metadata(path_from_string(path)).and_then(|m| m.modified()).ok();
+        if let Some((cached_mtime, language)) = self.entries.get(path)
+            && mtime.is_some()
+            && *cached_mtime == mtime
+        {
+            return language.clone();
+        }
+        let language = detect_language_for_path(path);
+        self.entries.insert(path.to_string(), (mtime, language.clone()));
+        language
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+// Bytes that aren't valid UTF-8 are stored as U+EF80..U+EFFF (private use), one char per byte.
+// A real character from that range is stored as its three UTF-8 bytes the same way, so every
+// stored string decodes to exactly one path.
+const RAW_BYTE_BASE: u32 = 0xEF00;
+
+/// Text form of a path for the artifact list and the database that `path_from_string` turns back
+/// into the same path, even when the file name isn't valid UTF-8.
+#[cfg(unix)]
+pub fn path_to_string(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let mut text = String::new();
+    let escape = |b: &u8| char::from_u32(RAW_BYTE_BASE + *b as u32);
+    for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if is_raw_byte(c) {
+                text.extend(c.encode_utf8(&mut [0; 4]).as_bytes().iter().filter_map(escape));
+            } else {
+                text.push(c);
+            }
+        }
+        text.extend(chunk.invalid().iter().filter_map(escape));
+    }
+    text
+}
+
+#[cfg(not(unix))]
+pub fn path_to_string(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[cfg(unix)]
+pub fn path_from_string(text: &str) -> PathBuf {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+    if !text.chars().any(is_raw_byte) {
+        return PathBuf::from(text);
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match raw_byte(c) {
+            Some(byte) => bytes.push(byte),
+            None => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+pub fn path_from_string(text: &str) -> PathBuf {
+    PathBuf::from(text)
+}
+
+fn is_raw_byte(c: char) -> bool {
+    (RAW_BYTE_BASE + 0x80..=RAW_BYTE_BASE + 0xFF).contains(&(c as u32))
+}
+
+/// The path byte a stored char stands for, when it is one of the escapes `path_to_string` writes.
+pub fn raw_byte(c: char) -> Option<u8> {
+    is_raw_byte(c).then(|| (c as u32 - RAW_BYTE_BASE) as u8)
+}
+
+/// What to show for a stored path: the real path, with bytes that aren't UTF-8 as the
+/// replacement character.
+pub fn display_path(text: &str) -> String {
+    path_from_string(text).to_string_lossy().into_owned()
+}
+
+/// The command `r` would run to rebuild a project, picked from its manifest.
+pub fn rebuild_command(project_root: &Path) -> Option<&'static str> {
+    if project_root.join("Cargo.toml").exists() {
+        Some("cargo build")
+    } else if project_root.join("package.json").exists() {
+        Some("npm run build")
+    } else {
+        None
+    }
+}
+
+/// The scripts from package.json that `npm run build` really executes, in the order it runs
+/// them: `prebuild`, `build` and `postbuild`, each only when defined.
+pub fn npm_build_scripts(project_root: &Path) -> Vec<(&'static str, String)> {
+    let Some(scripts) = std::fs::read_to_string(project_root.join("package.json"))
+        .ok()
+        .and_then(|manifest| serde_json::from_str::<serde_json::Value>(&manifest).ok())
+        .and_then(|manifest| manifest.get("scripts").cloned())
+    else {
+        return vec![];
+    };
+    ["prebuild", "build", "postbuild"]
+        .into_iter()
+        .filter_map(|name| Some((name, scripts.get(name)?.as_str()?.to_string())))
+        .collect()
+}
+
+/// Lockfiles that pin a `node_modules` tree with its own copy of every package.
+const NODE_LOCKFILES: [&str; 3] = ["package-lock.json", "yarn.lock", "bun.lockb"];
+
+/// Hash of the lockfile next to a `node_modules` directory. Two trees with the same
+/// fingerprint were installed from the same pins, so their contents are very likely identical.
+/// None for other artifacts, for projects without a lockfile and for pnpm projects, whose
+/// `node_modules` already hard-links a shared store and so frees nothing by deduplicating.
+pub fn dependency_fingerprint(artifact_path: &str) -> Option<u64> {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let path = path_from_string(artifact_path);
+    if path.file_name()? != "node_modules" {
+        return None;
+    }
+    let project = path.parent()?;
+    if project.join("pnpm-lock.yaml").exists() {
+        return None;
+    }
+    NODE_LOCKFILES.iter().find_map(|name| {
+        let contents = std::fs::read(project.join(name)).ok()?;
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        contents.hash(&mut hasher);
+        Some(hasher.finish())
+    })
+}
+
+/// Groups of two or more paths sharing a fingerprint, in first-seen order.
+pub fn duplicate_groups(fingerprints: &[(String, u64)]) -> Vec<Vec<String>> {
+    let mut groups: Vec<(u64, Vec<String>)> = vec![];
+    for (path, fingerprint) in fingerprints {
+        match groups.iter_mut().find(|(f, _)| f == fingerprint) {
+            Some((_, paths)) => paths.push(path.clone()),
+            None => groups.push((*fingerprint, vec![path.clone()])),
+        }
+    }
+    groups.into_iter().map(|(_, paths)| paths).filter(|paths| paths.len() > 1).collect()
+}
+
+/// Space available to unprivileged users on the filesystem holding `path`.
+#[cfg(unix)]
+pub fn free_space_bytes(path: &str) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    let path = CString::new(path_from_string(path).as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stats` is a valid, writable statvfs
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space_bytes(_path: &str) -> Option<u64> {
+    None
+}
+
+/// Oldest-first picks from `candidates` (path, size) until `free` would reach `target`.
+pub fn free_space_plan(candidates: &[(String, u64)], free: u64, target: u64) -> Vec<String> {
+    let mut freed = 0;
+    candidates
+        .iter()
+        .take_while(|(_, size)| {
+            let needed = free + freed < target;
+            freed += size;
+            needed
+        })
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Whether the project an artifact belongs to is gone: no manifest left next to it and nothing
+/// else but artifact directories and dotfiles. A project ratifact can't name (a `.csproj`,
+/// `.sln`, `settings.gradle`, `deno.json`, plain sources...) still has files, so it isn't orphaned.
+pub fn is_orphaned(project_path: &str) -> bool {
+    if detect_language_for_path(project_path) != "Unknown" {
+        return false;
+    }
+    let Ok(entries) = std::fs::read_dir(path_from_string(project_path)) else {
+        return false;
+    };
+    entries.flatten().all(|entry| {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        ARTIFACT_DIRS.contains(&name.as_ref()) || (name.starts_with('.') && name != ".git")
+    })
+}
+
 pub fn calculate_dir_size(path: &str) -> u64 {
     use std::fs;
-    WalkDir::new(path)
+    WalkDir::new(path_from_string(path))
         .into_iter()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
@@ -46,3 +257,312 @@ pub fn calculate_dir_size(path: &str) -> u64 {
         .map(|m| m.len())
         .sum()
 }
+
+pub fn subdirectory_sizes(path: &str) -> Vec<(String, u64)> {
+    // Immediate children only, e.g. target/debug and target/release
+    let mut entries: Vec<(String, u64)> = std::fs::read_dir(path_from_string(path))
+        .map(|dir| {
+            dir.flatten()
+                .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                .map(|e| {
+                    let child = path_to_string(&e.path());
+                    let size = calculate_dir_size(&child);
+                    (child, size)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    entries
+}
+
+/// Upper bound on directory entries visited by `largest_files`, so huge node_modules trees stay responsive.
+pub const LARGEST_FILES_WALK_LIMIT: usize = 200_000;
+
+pub fn largest_files(path: &str, count: usize, walk_limit: usize) -> (Vec<(String, u64)>, bool) {
+    // Returns the biggest files (relative to `path`) and whether the walk stopped early
+    let mut files = Vec::new();
+    let mut truncated = false;
+    let root = path_from_string(path);
+    for (visited, entry) in WalkDir::new(&root).into_iter().filter_map(|e| e.ok()).enumerate() {
+        if visited >= walk_limit {
+            truncated = true;
+            break;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path()).display().to_string();
+        files.push((relative, metadata.len()));
+    }
+    files.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    files.truncate(count);
+    (files, truncated)
+}
+
+pub fn truncate_middle(path: &str, max_width: usize) -> String {
+    // Keeps the first component and as much of the tail as fits: /home/.../deep/target
+    let path = &display_path(path);
+    if path.chars().count() <= max_width {
+        return path.to_string();
+    }
+    const ELLIPSIS: &str = "...";
+    let sep = std::path::MAIN_SEPARATOR;
+    let parts: Vec<&str> = path.split(sep).collect();
+    let leaf = parts.last().copied().unwrap_or(path);
+    let leaf_len = leaf.chars().count();
+    if leaf_len + ELLIPSIS.len() + 1 > max_width {
+        // Not even the leaf fits; keep its end, which usually tells target from target-old
+        let keep = max_width.saturating_sub(ELLIPSIS.len());
+        let tail: String = leaf.chars().skip(leaf_len.saturating_sub(keep)).collect();
+        return format!("{}{}", ELLIPSIS, tail);
+    }
+
+    // Absolute paths start with an empty component, so take up to the first named one
+    let head_len = parts.iter().position(|p| !p.is_empty()).map_or(0, |i| i + 1);
+    let head = if head_len < parts.len() - 1 { parts[..head_len].join(&sep.to_string()) } else { String::new() };
+    let mut tail = vec![leaf];
+    let fits = |head: &str, tail: &[&str]| {
+        let tail_len: usize = tail.iter().map(|p| p.chars().count() + 1).sum();
+        let head_len = if head.is_empty() { 0 } else { head.chars().count() + 1 };
+        head_len + ELLIPSIS.len() + tail_len <= max_width
+    };
+    let head = if fits(&head, &tail) { head } else { String::new() };
+    let middle_start = if head.is_empty() { 0 } else { head_len };
+    for part in parts[middle_start..parts.len() - 1].iter().rev() {
+        let mut candidate = vec![*part];
+        candidate.extend(tail.iter());
+        if !fits(&head, &candidate) {
+            break;
+        }
+        tail = candidate;
+    }
+    let tail = tail.join(&sep.to_string());
+    if head.is_empty() {
+        format!("{}{}{}", ELLIPSIS, sep, tail)
+    } else {
+        format!("{}{}{}{}{}", head, sep, ELLIPSIS, sep, tail)
+    }
+}
+
+pub fn vanished_artifacts(paths: &[String], scan_roots: &[String]) -> Vec<String> {
+    // Only trust a missing artifact when the scan path it lives under is still there;
+    // an unmounted drive would otherwise wipe its whole history. Paths outside every scan
+    // path can't be checked that way, so they are kept.
+    paths
+        .iter()
+        .filter(|path| {
+            if path_from_string(path).exists() {
+                return false;
+            }
+            let path = Path::new(path);
+            scan_roots
+                .iter()
+                .filter(|root| path.starts_with(root))
+                .max_by_key(|root| root.len())
+                .is_some_and(|root| root_is_available(&path_from_string(root)))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether a scan path holds its drive's contents: an unmounted mount point still exists, but
+/// as an empty directory on its parent's device.
+fn root_is_available(root: &Path) -> bool {
+    if std::fs::read_dir(root).is_ok_and(|mut entries| entries.next().is_some()) {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let (Ok(own), Some(Ok(parent))) = (std::fs::metadata(root), root.parent().map(std::fs::metadata)) {
+            return own.dev() != parent.dev();
+        }
+    }
+    false
+}
+
+/// Newest modification time of anything in the tree at `path`, the directory itself included.
+/// Reading the files (as the scan's size walk does) leaves it alone, unlike atime.
+pub fn content_modified(path: &str) -> Option<SystemTime> {
+    WalkDir::new(path_from_string(path))
+        .follow_links(false)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// `candidates` least recently modified first, leaving out the ones modified within
+/// `idle_days` and the ones already gone from disk.
+pub fn least_recently_modified(candidates: &[(String, u64)], idle_days: u32) -> Vec<(String, u64)> {
+    let Some(cutoff) = SystemTime::now().checked_sub(Duration::from_secs(idle_days as u64 * 24 * 60 * 60)) else {
+        return vec![];
+    };
+    let mut dated: Vec<(SystemTime, &(String, u64))> = candidates
+        .iter()
+        .filter_map(|candidate| content_modified(&candidate.0).map(|modified| (modified, candidate)))
+        .filter(|(modified, _)| *modified < cutoff)
+        .collect();
+    dated.sort_by_key(|(modified, _)| *modified);
+    dated.into_iter().map(|(_, candidate)| candidate.clone()).collect()
+}
+
+/// Whether anything in the tree at `path` was modified within `days`. Not atime: the scan
+/// reads every directory and file while sizing, which would make every artifact look used.
+pub fn used_within_days(path: &str, days: u32) -> bool {
+    modified_within_days(content_modified(path), days)
+}
+
+/// Whether a `content_modified` time falls within the last `days`, for checking one walk
+/// against several windows.
+pub fn modified_within_days(last_used: Option<SystemTime>, days: u32) -> bool {
+    let window = Duration::from_secs(days as u64 * 24 * 60 * 60);
+    match last_used.and_then(|t| SystemTime::now().duration_since(t).ok()) {
+        Some(age) => age < window,
+        // Timestamps in the future also mean "recently touched"
+        None => last_used.is_some(),
+    }
+}
+
+pub fn format_relative_time(time: DateTime<Utc>) -> String {
+    relative_time_since(time, Utc::now())
+}
+
+pub fn relative_time_since(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - time).num_seconds();
+    // Clock skew between the DB and this machine can put recent builds slightly in the future
+    if seconds < 60 {
+        return if seconds < -60 { "in the future".to_string() } else { "just now".to_string() };
+    }
+    let minutes = seconds / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+    if minutes < 60 {
+        format!("{}m ago", minutes)
+    } else if hours < 24 {
+        format!("{}h ago", hours)
+    } else if days < 30 {
+        format!("{}d ago", days)
+    } else if days < 365 {
+        format!("{}mo ago", days / 30)
+    } else {
+        format!("{}y ago", days / 365)
+    }
+}
+
+pub fn normalize_path(path: &str) -> String {
+    // Canonicalize when possible so excludes compare against real absolute paths
+    std::fs::canonicalize(path_from_string(path))
+        .map(|p| path_to_string(&p))
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn is_under_any(path: &str, roots: &[String]) -> bool {
+    // Component-wise prefix match: /a/b covers /a/b/c but not /a/bc
+    let p = Path::new(path);
+    roots.iter().any(|root| p.starts_with(root))
+}
+
+/// Separates the prefix from the directory name in a type exclusion (`/home/me/work/**/node_modules`).
+const TYPE_EXCLUSION_SEPARATOR: &str = "/**/";
+
+pub fn type_exclusion(prefix: &str, name: &str) -> String {
+    format!("{}{}{}", prefix.trim_end_matches(['/', '\\']), TYPE_EXCLUSION_SEPARATOR, name)
+}
+
+/// The directory right below `scan_root` that `path` lives in, e.g. `~/Downloads` for
+/// `~/Downloads/unpacked/app/node_modules` found while scanning `~`. None when `path` isn't
+/// under `scan_root` or sits directly in it, where the area would be the artifact itself.
+pub fn area_exclusion(path: &str, scan_root: &str) -> Option<String> {
+    let rest = Path::new(path).strip_prefix(scan_root).ok()?;
+    let mut components = rest.components();
+    let first = components.next()?;
+    components.next()?;
+    Some(path_to_string(&Path::new(scan_root).join(first)))
+}
+
+pub fn is_path_excluded(path: &str, excluded_paths: &[String]) -> bool {
+    let p = Path::new(path);
+    excluded_paths.iter().any(|entry| match entry.split_once(TYPE_EXCLUSION_SEPARATOR) {
+        // Any directory with that name below the prefix, and everything inside it
+        Some((prefix, name)) => p
+            .strip_prefix(prefix)
+            .is_ok_and(|rest| rest.components().any(|c| c.as_os_str() == name)),
+        None => is_under_any(path, std::slice::from_ref(entry)),
+    })
+}
+
+pub fn is_path_protected(path: &str, protected_paths: &[String]) -> bool {
+    // Protecting a project directory protects every artifact inside it
+    is_under_any(path, protected_paths)
+}
+
+pub fn is_virtualenv(path: &Path) -> bool {
+    // venv, .venv, env, ... are recognised by content; matching names like "env" would be too broad
+    path.join("pyvenv.cfg").is_file()
+}
+
+/// First bytes of every CACHEDIR.TAG, per https://bford.info/cachedir/
+const CACHEDIR_TAG_SIGNATURE: &[u8] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+pub fn has_cachedir_tag(path: &Path) -> bool {
+    // Only the signature counts; a stray empty CACHEDIR.TAG doesn't mark a directory as disposable
+    use std::io::Read;
+    let Ok(file) = std::fs::File::open(path.join("CACHEDIR.TAG")) else {
+        return false;
+    };
+    let mut head = Vec::with_capacity(CACHEDIR_TAG_SIGNATURE.len());
+    file.take(CACHEDIR_TAG_SIGNATURE.len() as u64).read_to_end(&mut head).is_ok() && head == CACHEDIR_TAG_SIGNATURE
+}
+
+pub fn is_skipped_hidden_dir(name: &str, artifact_dirs: &[&str]) -> bool {
+    // Dot directories are pruned unless they are artifacts themselves (.next, .gradle, ...)
+    name.starts_with('.') && name != "." && name != ".." && !artifact_dirs.contains(&name)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    // Decimal units, matching the MB figures shown in the charts
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+pub fn format_scan_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+pub fn describe_walk_error(err: &walkdir::Error) -> String {
+    let path = err
+        .path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let reason = if err.loop_ancestor().is_some() {
+        "filesystem loop".to_string()
+    } else {
+        match err.io_error().map(|e| e.kind()) {
+            Some(std::io::ErrorKind::PermissionDenied) => "permission denied".to_string(),
+            Some(std::io::ErrorKind::NotFound) => "not found".to_string(),
+            Some(_) => err.io_error().map(|e| e.to_string()).unwrap_or_default(),
+            None => err.to_string(),
+        }
+    };
+    format!("Skipped {}: {}", path, reason)
+}
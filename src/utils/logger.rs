@@ -2,9 +2,12 @@
 
 use std::fs::OpenOptions;
 use std::io::Write;
+use crate::config::paths::data_dir;
 
 pub fn log_to_file(message: &str) {
-    let log_path = "/tmp/ratifact-by-neura-ai.log";
+    let log_dir = data_dir();
+    let _ = std::fs::create_dir_all(&log_dir);
+    let log_path = log_dir.join("ratifact.log");
     if let Ok(mut file) = OpenOptions::new()
         .create(true)
         .append(true)
@@ -2,6 +2,12 @@
 
 pub mod logger;
 pub mod helpers;
+pub mod docker;
+pub mod deletion;
+pub mod desktop;
+pub mod report;
 
 // Re-export commonly used functions
-pub use helpers::{detect_language_for_path, calculate_dir_size};
+pub use helpers::{LanguageCache, calculate_dir_size, is_orphaned, describe_walk_error, normalize_path, is_path_excluded, is_path_protected, type_exclusion, area_exclusion, is_skipped_hidden_dir, is_virtualenv, has_cachedir_tag, format_bytes, format_scan_duration, format_relative_time, largest_files, LARGEST_FILES_WALK_LIMIT, subdirectory_sizes, truncate_middle, used_within_days, content_modified, modified_within_days, vanished_artifacts, path_to_string, path_from_string, display_path, raw_byte, rebuild_command, npm_build_scripts, free_space_bytes, free_space_plan, least_recently_modified, dependency_fingerprint, duplicate_groups};
+#[cfg(test)]
+pub use helpers::{detect_language_for_path, relative_time_since};
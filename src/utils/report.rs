@@ -0,0 +1,64 @@
+// Scan report files
+
+use crate::utils::logger::log_to_file;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Serialize)]
+pub struct ScanReportFile {
+    pub generated_at: String,
+    pub duration_ms: u64,
+    pub artifact_count: usize,
+    pub total_bytes: u64,
+    pub artifacts: Vec<ReportedArtifact>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportedArtifact {
+    pub path: String,
+    /// None when the artifact has no recorded size yet.
+    pub size_bytes: Option<u64>,
+}
+
+impl ScanReportFile {
+    /// Largest artifacts first, like the Charts panel.
+    pub fn new(generated_at: chrono::DateTime<chrono::Local>, duration_ms: u64, mut artifacts: Vec<ReportedArtifact>) -> Self {
+        artifacts.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.path.cmp(&b.path)));
+        ScanReportFile {
+            generated_at: generated_at.to_rfc3339(),
+            duration_ms,
+            artifact_count: artifacts.len(),
+            total_bytes: artifacts.iter().filter_map(|a| a.size_bytes).sum(),
+            artifacts,
+        }
+    }
+}
+
+/// Writes `scan-<date>-<time>.json` into `dir`, creating it if needed, and returns the file's path.
+pub fn write_scan_report(dir: &Path, report: &ScanReportFile, generated_at: chrono::DateTime<chrono::Local>) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    // One file per scan; seconds keep two scans on the same day apart
+    let path = dir.join(format!("scan-{}.json", generated_at.format("%Y-%m-%d-%H%M%S")));
+    std::fs::write(&path, serde_json::to_string_pretty(report).map_err(std::io::Error::other)?)?;
+    Ok(path)
+}
+
+/// Writes the scan report into `dir` and returns the line to log either way.
+pub fn save_scan_report(dir: &str, artifacts: &[String], sizes: &HashMap<String, u64>, duration: Duration) -> String {
+    let artifacts = artifacts
+        .iter()
+        .map(|path| ReportedArtifact { path: path.clone(), size_bytes: sizes.get(path).copied() })
+        .collect();
+    let now = chrono::Local::now();
+    let report = ScanReportFile::new(now, duration.as_millis() as u64, artifacts);
+    match write_scan_report(Path::new(dir), &report, now) {
+        Ok(path) => format!("Scan report written to {}", path.display()),
+        Err(e) => {
+            let message = format!("Could not write scan report to {}: {}", dir, e);
+            log_to_file(&message);
+            message
+        }
+    }
+}